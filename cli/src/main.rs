@@ -0,0 +1,128 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use java_generator::{JavaGenOptions, generate_java_with_options, write_java_to_dir};
+use parser::{ProtoModel, parse_proto_file, parse_proto_str, validate};
+
+const USAGE: &str = "usage: proto-to-pojo --proto <file.proto|-> [--out <dir|->] [--check]";
+
+// `-` as a path means stdin for --proto and stdout for --out
+fn is_stdio(p: &Path) -> bool {
+    p.as_os_str() == "-"
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    proto: Option<PathBuf>,
+    out: Option<PathBuf>,
+    check: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--proto" => args.proto = Some(it.next().ok_or("--proto needs a value")?.into()),
+            "--out" => args.out = Some(it.next().ok_or("--out needs a value")?.into()),
+            "--check" => args.check = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown argument `{}`\n{}", other, USAGE)),
+        }
+    }
+    Ok(args)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(a) => a,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    let Some(proto) = args.proto else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let source = if is_stdio(&proto) {
+        "<stdin>".to_string()
+    } else {
+        proto.display().to_string()
+    };
+    let model = match read_model(&proto) {
+        Ok(m) => m,
+        // file errors already name their path (ParseError::InFile)
+        Err(e) if is_stdio(&proto) => {
+            eprintln!("{}: {}", source, e);
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // --check: lint only, never touch the output tree
+    if args.check {
+        return match validate(&model) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("{}: {}", source, e);
+                }
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // stdin input streams to stdout unless an output directory is given
+    let out = args
+        .out
+        .unwrap_or_else(|| PathBuf::from(if is_stdio(&proto) { "-" } else { "." }));
+    if is_stdio(&out) {
+        return write_to_stdout(&model);
+    }
+    match write_java_to_dir(&model, &out, &JavaGenOptions::default()) {
+        Ok(files) => {
+            for f in files {
+                println!("{}", f.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read_model(proto: &Path) -> Result<ProtoModel, parser::ParseError> {
+    if !is_stdio(proto) {
+        return parse_proto_file(proto);
+    }
+    let mut src = String::new();
+    std::io::stdin().read_to_string(&mut src)?;
+    parse_proto_str(&src)
+}
+
+// A single generated file is written as-is; several are each preceded by a
+// `// <path>` line so the stream can be split again.
+fn write_to_stdout(model: &ProtoModel) -> ExitCode {
+    let files = generate_java_with_options(model, &JavaGenOptions::default());
+    let mut stdout = std::io::stdout().lock();
+    for (path, code) in &files {
+        let res = if files.len() == 1 {
+            stdout.write_all(code.as_bytes())
+        } else {
+            write!(stdout, "// {}\n{}", path, code)
+        };
+        if let Err(e) = res {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use parser::{
+    Enum, EnumValue, Extension, Field, FieldLabel, FieldType, Import, ImportKind, Message,
+    OptionValue, ProtoModel, ProtoSyntax, RpcMethod, ScalarType, Service, TypeDecl,
+};
+
+use crate::wire;
+
+// FileDescriptorProto (descriptor.proto) -> ProtoModel. Comments live in
+// source_code_info and are not carried over.
+pub fn decode_file(bytes: &[u8]) -> Result<(String, ProtoModel), String> {
+    let mut name = String::new();
+    let mut model = ProtoModel {
+        syntax: ProtoSyntax::Proto2,
+        ..Default::default()
+    };
+    let (mut deps, mut public, mut weak) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut messages, mut enums, mut extensions) = (Vec::new(), Vec::new(), Vec::new());
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => name = v.as_string()?,
+            2 => model.package = Some(v.as_string()?),
+            3 => deps.push(v.as_string()?),
+            4 => messages.push(v.as_bytes()),
+            5 => enums.push(v.as_bytes()),
+            6 => model.services.push(decode_service(v.as_bytes())?),
+            7 => extensions.push(v.as_bytes()),
+            10 => public.push(v.as_u64() as usize),
+            11 => weak.push(v.as_u64() as usize),
+            12 if v.as_string()? == "proto3" => model.syntax = ProtoSyntax::Proto3,
+            _ => {}
+        }
+    }
+
+    for (i, path) in deps.into_iter().enumerate() {
+        let kind = if public.contains(&i) {
+            ImportKind::Public
+        } else if weak.contains(&i) {
+            ImportKind::Weak
+        } else {
+            ImportKind::Normal
+        };
+        model.imports.push(Import { path, kind });
+    }
+    for m in messages {
+        decode_message(m, None, model.syntax, &mut model.types)?;
+    }
+    for e in enums {
+        model.types.push(TypeDecl::Enum(decode_enum(e, None)?));
+    }
+    for ext in extensions {
+        let (field, extendee) = decode_field(ext, model.syntax, &[])?;
+        match model.extensions.iter_mut().find(|e| e.extendee == extendee) {
+            Some(e) => e.fields.push(field),
+            None => model.extensions.push(Extension {
+                extendee,
+                fields: vec![field],
+            }),
+        }
+    }
+    Ok((name, model))
+}
+
+// Like the parser, nested types are pushed before their parent.
+fn decode_message(
+    bytes: &[u8],
+    parent: Option<&str>,
+    syntax: ProtoSyntax,
+    types: &mut Vec<TypeDecl>,
+) -> Result<(), String> {
+    let entries = wire::fields(bytes)?;
+    let mut message = Message::default();
+    let mut oneofs = Vec::new();
+    let mut map_entries = Vec::new();
+    for (number, v) in &entries {
+        match number {
+            1 => message.name = qualify(parent, &v.as_string()?),
+            8 => oneofs.push(string_field(v.as_bytes(), 1)?),
+            _ => {}
+        }
+    }
+    for (number, v) in &entries {
+        match number {
+            2 => message
+                .fields
+                .push(decode_field(v.as_bytes(), syntax, &oneofs)?.0),
+            3 if is_map_entry(v.as_bytes())? => {
+                decode_message(v.as_bytes(), Some(&message.name), syntax, &mut map_entries)?
+            }
+            3 => decode_message(v.as_bytes(), Some(&message.name), syntax, types)?,
+            4 => types.push(TypeDecl::Enum(decode_enum(
+                v.as_bytes(),
+                Some(&message.name),
+            )?)),
+            5 => {
+                let (mut start, mut end) = (0, 0);
+                for (n, r) in wire::fields(v.as_bytes())? {
+                    match n {
+                        1 => start = r.as_u64() as u32,
+                        2 => end = r.as_u64() as u32,
+                        _ => {}
+                    }
+                }
+                // end is exclusive in descriptors
+                message.extension_ranges.push(start..=end.saturating_sub(1));
+            }
+            7 => message.options = decode_message_options(v.as_bytes())?,
+            _ => {}
+        }
+    }
+    for f in &mut message.fields {
+        if let Some(ty) = map_type(f, &map_entries) {
+            f.ty = ty;
+            f.label = None;
+        }
+    }
+    types.push(TypeDecl::Message(message));
+    Ok(())
+}
+
+// DescriptorProto.options (7) -> MessageOptions.map_entry (7)
+fn is_map_entry(bytes: &[u8]) -> Result<bool, String> {
+    for (number, v) in wire::fields(bytes)? {
+        if number == 7 {
+            return Ok(wire::fields(v.as_bytes())?
+                .iter()
+                .any(|(n, o)| *n == 7 && o.as_u64() != 0));
+        }
+    }
+    Ok(false)
+}
+
+// protoc lowers `map<K, V> x` to `repeated XEntry x` plus a nested
+// `XEntry { K key = 1; V value = 2; }` marked map_entry. Undo that: return the
+// field's map type from its (undeclared) entry message.
+fn map_type(f: &Field, map_entries: &[TypeDecl]) -> Option<FieldType> {
+    let FieldType::Custom(type_name) = &f.ty else {
+        return None;
+    };
+    let entry = map_entries.iter().find_map(|t| match t {
+        TypeDecl::Message(e)
+            if *type_name == e.name || type_name.ends_with(&format!(".{}", e.name)) =>
+        {
+            Some(e)
+        }
+        _ => None,
+    })?;
+    let FieldType::Scalar(key) = &entry.field_by_tag(1)?.ty else {
+        return None;
+    };
+    Some(FieldType::Map {
+        key: key.clone(),
+        value: Box::new(entry.field_by_tag(2)?.ty.clone()),
+    })
+}
+
+// FieldDescriptorProto -> (field, extendee)
+fn decode_field(
+    bytes: &[u8],
+    syntax: ProtoSyntax,
+    oneofs: &[String],
+) -> Result<(Field, String), String> {
+    let (mut name, mut extendee, mut type_name) = (String::new(), String::new(), String::new());
+    let (mut order, mut label, mut ty) = (0, 1, 0);
+    let (mut default, mut json_name, mut oneof_index) = (None, None, None);
+    let mut proto3_optional = false;
+    let mut options = Vec::new();
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => name = v.as_string()?,
+            2 => extendee = v.as_string()?.trim_start_matches('.').to_string(),
+            3 => order = v.as_u64() as u32,
+            4 => label = v.as_u64(),
+            5 => ty = v.as_u64(),
+            6 => type_name = v.as_string()?.trim_start_matches('.').to_string(),
+            7 => default = Some(v.as_string()?),
+            8 => options = decode_field_options(v.as_bytes())?,
+            9 => oneof_index = Some(v.as_u64() as usize),
+            10 => json_name = Some(v.as_string()?),
+            17 => proto3_optional = v.as_u64() != 0,
+            _ => {}
+        }
+    }
+
+    let scalar = |st| FieldType::Scalar(st);
+    let field_type = match ty {
+        1 => scalar(ScalarType::Double),
+        2 => scalar(ScalarType::Float),
+        3 => scalar(ScalarType::Int64),
+        4 => scalar(ScalarType::Uint64),
+        5 => scalar(ScalarType::Int32),
+        6 => scalar(ScalarType::Fixed64),
+        7 => scalar(ScalarType::Fixed32),
+        8 => scalar(ScalarType::Bool),
+        9 => scalar(ScalarType::String),
+        12 => scalar(ScalarType::Bytes),
+        13 => scalar(ScalarType::Uint32),
+        15 => scalar(ScalarType::Sfixed32),
+        16 => scalar(ScalarType::Sfixed64),
+        17 => scalar(ScalarType::Sint32),
+        18 => scalar(ScalarType::Sint64),
+        10 | 11 | 14 => FieldType::Custom(type_name),
+        other => return Err(format!("field `{}`: unknown type {}", name, other)),
+    };
+
+    // proto3 optional fields sit in a synthetic oneof, which the IR has no notion of
+    let oneof = oneof_index
+        .filter(|_| !proto3_optional)
+        .and_then(|i| oneofs.get(i).cloned());
+    // protoc reports singular fields as LABEL_OPTIONAL; the IR keeps the
+    // label only where it was written
+    let label = match label {
+        3 => Some(FieldLabel::Repeated),
+        2 => Some(FieldLabel::Required),
+        _ if oneof.is_some() => None,
+        _ if syntax == ProtoSyntax::Proto3 && !proto3_optional => None,
+        _ => Some(FieldLabel::Optional),
+    };
+    if let Some(d) = default {
+        options.insert(0, ("default".to_string(), default_value(&field_type, d)));
+    }
+    let mut field = Field {
+        ty: field_type,
+        name,
+        order,
+        label,
+        trailing_doc: None,
+        options,
+        json_name: None,
+        oneof,
+        features: HashMap::new(),
+    };
+    // protoc always fills json_name; only an override differs from the default
+    if let Some(j) = json_name.filter(|j| *j != field.json_key()) {
+        field
+            .options
+            .push(("json_name".to_string(), OptionValue::Str(j.clone())));
+        field.json_name = Some(j);
+    }
+    Ok((field, extendee))
+}
+
+// FieldOptions: only the well-known options the IR gives meaning to
+fn decode_field_options(bytes: &[u8]) -> Result<Vec<(String, OptionValue)>, String> {
+    let mut options = Vec::new();
+    for (number, v) in wire::fields(bytes)? {
+        let ident = |names: &[&str]| {
+            let name = names.get(v.as_u64() as usize).copied().unwrap_or_default();
+            OptionValue::Ident(name.to_string())
+        };
+        let option = match number {
+            1 => ("ctype", ident(&["STRING", "CORD", "STRING_PIECE"])),
+            2 => ("packed", OptionValue::Bool(v.as_u64() != 0)),
+            3 => ("deprecated", OptionValue::Bool(v.as_u64() != 0)),
+            5 => ("lazy", OptionValue::Bool(v.as_u64() != 0)),
+            6 => ("jstype", ident(&["JS_NORMAL", "JS_STRING", "JS_NUMBER"])),
+            _ => continue,
+        };
+        options.push((option.0.to_string(), option.1));
+    }
+    Ok(options)
+}
+
+// MessageOptions: deprecated and map_entry
+fn decode_message_options(bytes: &[u8]) -> Result<Vec<(String, OptionValue)>, String> {
+    let mut options = Vec::new();
+    for (number, v) in wire::fields(bytes)? {
+        let name = match number {
+            3 => "deprecated",
+            7 => "map_entry",
+            _ => continue,
+        };
+        options.push((name.to_string(), OptionValue::Bool(v.as_u64() != 0)));
+    }
+    Ok(options)
+}
+
+fn default_value(ty: &FieldType, text: String) -> OptionValue {
+    match ty {
+        FieldType::Scalar(ScalarType::String | ScalarType::Bytes) => OptionValue::Str(text),
+        FieldType::Scalar(ScalarType::Bool) => OptionValue::Bool(text == "true"),
+        FieldType::Scalar(ScalarType::Float | ScalarType::Double) => text
+            .parse()
+            .map(OptionValue::Float)
+            .unwrap_or(OptionValue::Ident(text)),
+        FieldType::Scalar(_) => text
+            .parse()
+            .map(OptionValue::Int)
+            .unwrap_or(OptionValue::Ident(text)),
+        FieldType::Custom(_)
+        | FieldType::Message(_)
+        | FieldType::Enum(_)
+        | FieldType::Map { .. } => OptionValue::Ident(text),
+    }
+}
+
+fn decode_enum(bytes: &[u8], parent: Option<&str>) -> Result<Enum, String> {
+    let mut en = Enum::default();
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => en.name = qualify(parent, &v.as_string()?),
+            2 => en.values.push(decode_enum_value(v.as_bytes())?),
+            4 => {
+                let (mut start, mut end) = (0, 0);
+                for (n, r) in wire::fields(v.as_bytes())? {
+                    match n {
+                        1 => start = r.as_u64() as i32,
+                        2 => end = r.as_u64() as i32,
+                        _ => {}
+                    }
+                }
+                en.reserved_numbers.push(start..=end); // inclusive for enums
+            }
+            5 => en.reserved_names.push(v.as_string()?),
+            _ => {}
+        }
+    }
+    Ok(en)
+}
+
+fn decode_enum_value(bytes: &[u8]) -> Result<EnumValue, String> {
+    let mut value = EnumValue {
+        name: String::new(),
+        number: 0,
+        trailing_doc: None,
+        leading_doc: None,
+        options: Vec::new(),
+    };
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => value.name = v.as_string()?,
+            // negative int32s are sign-extended to 64 bits on the wire
+            2 => value.number = v.as_u64() as i32,
+            3 => {
+                for (n, o) in wire::fields(v.as_bytes())? {
+                    if n == 1 {
+                        value
+                            .options
+                            .push(("deprecated".to_string(), OptionValue::Bool(o.as_u64() != 0)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(value)
+}
+
+fn decode_service(bytes: &[u8]) -> Result<Service, String> {
+    let mut service = Service::default();
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => service.name = v.as_string()?,
+            2 => service.methods.push(decode_method(v.as_bytes())?),
+            _ => {}
+        }
+    }
+    Ok(service)
+}
+
+fn decode_method(bytes: &[u8]) -> Result<RpcMethod, String> {
+    let mut method = RpcMethod {
+        name: String::new(),
+        input_type: String::new(),
+        output_type: String::new(),
+        client_streaming: false,
+        server_streaming: false,
+        options: Vec::new(),
+    };
+    for (number, v) in wire::fields(bytes)? {
+        match number {
+            1 => method.name = v.as_string()?,
+            2 => method.input_type = v.as_string()?.trim_start_matches('.').to_string(),
+            3 => method.output_type = v.as_string()?.trim_start_matches('.').to_string(),
+            4 => {
+                for (n, o) in wire::fields(v.as_bytes())? {
+                    if n == 33 {
+                        method
+                            .options
+                            .push(("deprecated".to_string(), OptionValue::Bool(o.as_u64() != 0)));
+                    }
+                }
+            }
+            5 => method.client_streaming = v.as_u64() != 0,
+            6 => method.server_streaming = v.as_u64() != 0,
+            _ => {}
+        }
+    }
+    Ok(method)
+}
+
+fn string_field(bytes: &[u8], field: u32) -> Result<String, String> {
+    match wire::fields(bytes)?.into_iter().find(|(n, _)| *n == field) {
+        Some((_, v)) => v.as_string(),
+        None => Ok(String::new()),
+    }
+}
+
+fn qualify(parent: Option<&str>, name: &str) -> String {
+    match parent {
+        Some(p) => format!("{}.{}", p, name),
+        None => name.to_string(),
+    }
+}
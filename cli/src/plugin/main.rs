@@ -0,0 +1,90 @@
+// protoc plugin: reads a CodeGeneratorRequest on stdin and writes a
+// CodeGeneratorResponse on stdout, e.g.
+//   protoc --plugin=protoc-gen-pojo --pojo_out=builder:gen demo.proto
+
+mod descriptor;
+mod wire;
+
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use java_generator::{GeneratedFile, JavaGenOptions, TypeRegistry, generate_java_with_registry};
+
+// CodeGeneratorResponse.Feature.FEATURE_PROTO3_OPTIONAL
+const FEATURE_PROTO3_OPTIONAL: u64 = 1;
+
+fn main() -> ExitCode {
+    let mut input = Vec::new();
+    if let Err(e) = std::io::stdin().read_to_end(&mut input) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    // Generation problems go back to protoc in the response's `error` field;
+    // it reports them and fails the run.
+    let mut response = wire::Writer::default();
+    match generate(&input) {
+        Ok(files) => {
+            response.varint_field(2, FEATURE_PROTO3_OPTIONAL);
+            for (name, content) in files {
+                let mut file = wire::Writer::default();
+                file.bytes_field(1, name.as_bytes());
+                file.bytes_field(15, content.as_bytes());
+                response.bytes_field(15, &file.buf);
+            }
+        }
+        Err(e) => response.bytes_field(1, e.as_bytes()),
+    }
+    if let Err(e) = std::io::stdout().lock().write_all(&response.buf) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn generate(request: &[u8]) -> Result<Vec<GeneratedFile>, String> {
+    let mut to_generate = Vec::new();
+    let mut parameter = String::new();
+    let mut files = Vec::new();
+    for (number, v) in wire::fields(request)? {
+        match number {
+            1 => to_generate.push(v.as_string()?),
+            2 => parameter = v.as_string()?,
+            15 => files.push(descriptor::decode_file(v.as_bytes())?),
+            _ => {}
+        }
+    }
+    let opts = options_from_parameter(&parameter)?;
+
+    // proto_file carries every transitive dependency, so cross-file
+    // references resolve even for files we don't generate
+    let registry = TypeRegistry::from_models(files.iter().map(|(_, m)| m));
+    let mut out = Vec::new();
+    for name in &to_generate {
+        let (_, model) = files
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| format!("{}: not among the request's proto files", name))?;
+        out.extend(generate_java_with_registry(model, &opts, &registry));
+    }
+    Ok(out)
+}
+
+// `--pojo_out=builder,jackson:<dir>` passes "builder,jackson" here.
+fn options_from_parameter(parameter: &str) -> Result<JavaGenOptions, String> {
+    let mut opts = JavaGenOptions::default();
+    for p in parameter
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        match p {
+            "builder" => opts.builder = true,
+            "jackson" => opts.jackson = true,
+            "gson" => opts.gson = true,
+            "tag_constants" => opts.tag_constants = true,
+            other => return Err(format!("unknown plugin parameter `{}`", other)),
+        }
+    }
+    Ok(opts)
+}
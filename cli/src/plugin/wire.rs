@@ -0,0 +1,100 @@
+// Just enough of the protobuf wire format to read descriptors and write a
+// CodeGeneratorResponse.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_u64(&self) -> u64 {
+        match *self {
+            Value::Varint(v) | Value::Fixed64(v) => v,
+            Value::Fixed32(v) => v as u64,
+            Value::Bytes(_) => 0,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match *self {
+            Value::Bytes(b) => b,
+            _ => &[],
+        }
+    }
+
+    pub fn as_string(&self) -> Result<String, String> {
+        String::from_utf8(self.as_bytes().to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+/// Decode every (field number, value) pair of a message, in wire order.
+pub fn fields(mut buf: &[u8]) -> Result<Vec<(u32, Value<'_>)>, String> {
+    let mut out = Vec::new();
+    while !buf.is_empty() {
+        let key = varint(&mut buf)?;
+        let number = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Value::Varint(varint(&mut buf)?),
+            1 => Value::Fixed64(u64::from_le_bytes(take(&mut buf, 8)?.try_into().unwrap())),
+            2 => {
+                let len = varint(&mut buf)? as usize;
+                Value::Bytes(take(&mut buf, len)?)
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(take(&mut buf, 4)?.try_into().unwrap())),
+            wt => return Err(format!("unsupported wire type {} for field {}", wt, number)),
+        };
+        out.push((number, value));
+    }
+    Ok(out)
+}
+
+fn varint(buf: &mut &[u8]) -> Result<u64, String> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&b, rest) = buf.split_first().ok_or("truncated varint")?;
+        *buf = rest;
+        v |= u64::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err("varint too long".to_string())
+}
+
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8], String> {
+    if buf.len() < n {
+        return Err("truncated message".to_string());
+    }
+    let (head, rest) = buf.split_at(n);
+    *buf = rest;
+    Ok(head)
+}
+
+#[derive(Debug, Default)]
+pub struct Writer {
+    pub buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn varint_field(&mut self, number: u32, v: u64) {
+        self.varint(u64::from(number) << 3);
+        self.varint(v);
+    }
+
+    pub fn bytes_field(&mut self, number: u32, bytes: &[u8]) {
+        self.varint((u64::from(number) << 3) | 2);
+        self.varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn cli() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_proto-to-pojo"))
+}
+
+#[test]
+fn check_passes_on_valid_proto() {
+    let out = cli()
+        .args(["--check", "--proto", "tests/resources/valid.proto"])
+        .output()
+        .expect("run cli");
+    assert!(
+        out.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(out.stdout.is_empty(), "--check must not generate files");
+}
+
+#[test]
+fn check_fails_on_invalid_proto() {
+    let out = cli()
+        .args(["--check", "--proto", "tests/resources/invalid.proto"])
+        .output()
+        .expect("run cli");
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Kind: first enum value must be zero in proto3"));
+    assert!(stderr.contains("Item: tag 1 is used more than once"));
+    assert!(stderr.contains("Item.other: unknown type `Missing`"));
+}
+
+#[test]
+fn stdin_proto_generates_java_on_stdout() {
+    let mut child = cli()
+        .args(["--proto", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run cli");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"syntax = \"proto3\";\npackage demo;\nmessage Ping { string id = 1; }\n")
+        .unwrap();
+    let out = child.wait_with_output().expect("wait for cli");
+    assert!(
+        out.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("package demo;"));
+    assert!(stdout.contains("public class Ping {"));
+    assert!(stdout.contains("private String id;"));
+}
+
+#[test]
+fn parse_error_names_the_file_once() {
+    let out = cli()
+        .args(["--proto", "tests/resources/malformed.proto"])
+        .output()
+        .expect("run cli");
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.starts_with("tests/resources/malformed.proto: "),
+        "{}",
+        stderr
+    );
+    assert_eq!(stderr.matches("malformed.proto").count(), 1, "{}", stderr);
+}
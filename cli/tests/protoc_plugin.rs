@@ -0,0 +1,166 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Minimal protobuf encoder for building requests by hand.
+#[derive(Default)]
+struct Msg(Vec<u8>);
+
+impl Msg {
+    fn varint(mut self, number: u32, v: u64) -> Self {
+        push_varint(&mut self.0, u64::from(number) << 3);
+        push_varint(&mut self.0, v);
+        self
+    }
+
+    fn bytes(mut self, number: u32, b: &[u8]) -> Self {
+        push_varint(&mut self.0, (u64::from(number) << 3) | 2);
+        push_varint(&mut self.0, b.len() as u64);
+        self.0.extend_from_slice(b);
+        self
+    }
+
+    fn str(self, number: u32, s: &str) -> Self {
+        self.bytes(number, s.as_bytes())
+    }
+
+    fn msg(self, number: u32, m: Msg) -> Self {
+        self.bytes(number, &m.0)
+    }
+}
+
+fn push_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+// Pull every length-delimited `number` field out of `buf` (top level only).
+fn strings(mut buf: &[u8], number: u32) -> Vec<Vec<u8>> {
+    let mut out = Vec::new();
+    let read = |buf: &mut &[u8]| {
+        let mut v = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = buf[0];
+            *buf = &buf[1..];
+            v |= u64::from(b & 0x7f) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                return v;
+            }
+        }
+    };
+    while !buf.is_empty() {
+        let key = read(&mut buf);
+        match key & 7 {
+            0 => {
+                read(&mut buf);
+            }
+            2 => {
+                let len = read(&mut buf) as usize;
+                if (key >> 3) as u32 == number {
+                    out.push(buf[..len].to_vec());
+                }
+                buf = &buf[len..];
+            }
+            wt => panic!("unexpected wire type {}", wt),
+        }
+    }
+    out
+}
+
+fn run_plugin(request: &Msg) -> Vec<u8> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_protoc-gen-pojo"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run plugin");
+    child.stdin.take().unwrap().write_all(&request.0).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    out.stdout
+}
+
+fn field(name: &str, number: u64, label: u64, ty: u64) -> Msg {
+    Msg::default()
+        .str(1, name)
+        .varint(3, number)
+        .varint(4, label)
+        .varint(5, ty)
+}
+
+fn demo_file() -> Msg {
+    let status = Msg::default()
+        .str(1, "Status")
+        .msg(2, Msg::default().str(1, "UNKNOWN").varint(2, 0))
+        .msg(2, Msg::default().str(1, "OK").varint(2, 1));
+    // what protoc emits for `map<string, int32> counts = 5;`
+    let counts_entry = Msg::default()
+        .str(1, "CountsEntry")
+        .msg(2, field("key", 1, 1, 9))
+        .msg(2, field("value", 2, 1, 5))
+        .msg(7, Msg::default().varint(7, 1));
+    let ping = Msg::default()
+        .str(1, "Ping")
+        .msg(2, field("id", 1, 1, 3))
+        .msg(2, field("tags", 2, 3, 9))
+        .msg(2, field("status", 3, 1, 14).str(6, ".demo.Status"))
+        .msg(2, field("note", 4, 1, 9).varint(17, 1))
+        .msg(
+            2,
+            field("counts", 5, 3, 11).str(6, ".demo.Ping.CountsEntry"),
+        )
+        .msg(3, counts_entry);
+    Msg::default()
+        .str(1, "demo.proto")
+        .str(2, "demo")
+        .msg(4, ping)
+        .msg(5, status)
+        .str(12, "proto3")
+}
+
+#[test]
+fn request_generates_java_files() {
+    let request = Msg::default()
+        .str(1, "demo.proto")
+        .str(2, "builder")
+        .msg(15, demo_file());
+    let response = run_plugin(&request);
+
+    assert!(strings(&response, 1).is_empty(), "unexpected error");
+    let files: Vec<(String, String)> = strings(&response, 15)
+        .iter()
+        .map(|f| {
+            let text = |n| String::from_utf8(strings(f, n).remove(0)).unwrap();
+            (text(1), text(15))
+        })
+        .collect();
+    let names: Vec<&str> = files.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["demo/Ping.java", "demo/Status.java"]);
+
+    let ping = &files[0].1;
+    assert!(ping.contains("package demo;"));
+    assert!(ping.contains("private long id;"));
+    assert!(ping.contains("private List<String> tags;"));
+    assert!(ping.contains("private Status status;"));
+    assert!(ping.contains("public boolean hasNote()"));
+    assert!(ping.contains("private Map<String, Integer> counts;"));
+    assert!(!ping.contains("CountsEntry"));
+    assert!(ping.contains("public static Builder newBuilder()"));
+    assert!(files[1].1.contains("OK(1)"));
+}
+
+#[test]
+fn unknown_parameter_is_reported_as_error() {
+    let request = Msg::default()
+        .str(1, "demo.proto")
+        .str(2, "frobnicate")
+        .msg(15, demo_file());
+    let response = run_plugin(&request);
+
+    let error = String::from_utf8(strings(&response, 1).remove(0)).unwrap();
+    assert_eq!(error, "unknown plugin parameter `frobnicate`");
+    assert!(strings(&response, 15).is_empty());
+}
@@ -0,0 +1,191 @@
+use parser::Field;
+
+use crate::{JavaGenOptions, render_javadoc};
+
+// Under `checkstyle_compatible`, member names, `final` parameters and Javadoc
+// are rendered with each member; `layout` then only moves whitespace: one
+// statement per line and one blank line before each method and nested type.
+
+// Javadoc for a generated member; `text` is the summary, optionally followed
+// by a blank line and `@param`/`@return` tags. Nothing unless enabled.
+pub(crate) fn doc(opts: &JavaGenOptions, pad: &str, text: &str) -> String {
+    if opts.checkstyle_compatible {
+        render_javadoc(text, pad)
+    } else {
+        String::new()
+    }
+}
+
+// `type name`, declared `final` when enabled.
+pub(crate) fn param(opts: &JavaGenOptions, ty: &str, name: &str) -> String {
+    if opts.checkstyle_compatible {
+        format!("final {} {}", ty, name)
+    } else {
+        format!("{} {}", ty, name)
+    }
+}
+
+// How Javadoc refers to a field: `{@code created_at} (field 1)`.
+pub(crate) fn field_ref(f: &Field) -> String {
+    format!("{{@code {}}} (field {})", f.name, f.order)
+}
+
+pub(crate) fn layout(code: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in code.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        match member_kind(trimmed) {
+            Some(Member::Type) => {
+                separate(&mut out);
+                out.push(line.to_string());
+            }
+            Some(Member::Method) => {
+                separate(&mut out);
+                split_statements(&mut out, indent, trimmed);
+            }
+            None => out.push(line.to_string()),
+        }
+    }
+    let mut s = out.join("\n");
+    s.push('\n');
+    s
+}
+
+enum Member {
+    Type,
+    Method,
+}
+
+const MODIFIERS: [&str; 6] = [
+    "public ",
+    "private ",
+    "protected ",
+    "static ",
+    "final ",
+    "abstract ",
+];
+
+fn member_kind(line: &str) -> Option<Member> {
+    let is_type = |l: &str| {
+        [" class ", " enum ", " interface ", " record "]
+            .iter()
+            .any(|k| format!(" {}", l).contains(k))
+    };
+    let head = line.split(['=', '{']).next().unwrap_or(line);
+    if MODIFIERS.iter().any(|m| line.starts_with(m)) {
+        if is_type(head) {
+            return Some(Member::Type);
+        }
+        return (head.contains('(') && line.ends_with(['{', '}'])).then_some(Member::Method);
+    }
+    // enum constructor: `Status(int number) { ... }`
+    let ident_end = line.find('(')?;
+    let ident = &line[..ident_end];
+    let is_ctor = ident.starts_with(|c: char| c.is_ascii_uppercase())
+        && ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && line.ends_with('}');
+    is_ctor.then_some(Member::Method)
+}
+
+// Drop blank lines before the member about to be emitted (and before any
+// annotations or Javadoc already attached to it), then add exactly one unless
+// it opens a block.
+fn separate(out: &mut Vec<String>) {
+    let at = attached_start(out);
+    let mut blanks = 0;
+    while at > blanks && out[at - blanks - 1].trim().is_empty() {
+        blanks += 1;
+    }
+    out.drain(at - blanks..at);
+    let at = at - blanks;
+    if at > 0 && !out[at - 1].trim_end().ends_with('{') {
+        out.insert(at, String::new());
+    }
+}
+
+// Index where the lines attached to the next member (annotations, Javadoc) start.
+fn attached_start(out: &[String]) -> usize {
+    let mut at = out.len();
+    while at > 0 && out[at - 1].trim_start().starts_with('@') {
+        at -= 1;
+    }
+    if at > 0 && out[at - 1].trim_end().ends_with("*/") {
+        while at > 0 && !out[at - 1].trim_start().starts_with("/**") {
+            at -= 1;
+        }
+        at = at.saturating_sub(1);
+    }
+    at
+}
+
+// `void setX(final long value) { a; b; }` -> one statement per line.
+// Methods already spanning several lines are kept as they are.
+fn split_statements(out: &mut Vec<String>, indent: &str, line: &str) {
+    let Some(open) = body_start(line) else {
+        out.push(format!("{}{}", indent, line));
+        return;
+    };
+    let (head, body) = line.split_at(open);
+    let body = body.trim();
+    if body == "{" {
+        out.push(format!("{}{}", indent, line));
+        return;
+    }
+    out.push(format!("{}{} {{", indent, head.trim_end()));
+    let body = body.strip_prefix('{').unwrap_or(body);
+    let body = body.strip_suffix('}').unwrap_or(body).trim();
+    for stmt in split_top_level(body, ';') {
+        let stmt = stmt.trim();
+        if !stmt.is_empty() {
+            out.push(format!("{}    {};", indent, stmt));
+        }
+    }
+    out.push(format!("{}}}", indent));
+}
+
+// Byte index of the `{` opening the method body: the first one after the
+// parameter list closes.
+fn body_start(line: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in line.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '{' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Split on `sep` outside parentheses, generics, braces and string literals.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start, mut in_str, mut escaped) = (0i32, 0, false, false);
+    for (i, c) in s.char_indices() {
+        if in_str {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_str = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_str = true,
+            '(' | '<' | '{' | '[' => depth += 1,
+            ')' | '>' | '}' | ']' => depth -= 1,
+            c if c == sep && depth <= 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !s[start..].trim().is_empty() {
+        parts.push(&s[start..]);
+    }
+    parts
+}
@@ -0,0 +1,119 @@
+use parser::{Field, Message, ProtoModel, TypeDecl};
+
+use crate::{
+    JavaGenOptions, TypeScope, checkstyle, collect_type_imports, indent, is_map, is_repeated,
+    java_class_name, java_field_type, nested_enum_imports, render_enum_decl, render_imports,
+    render_package_line,
+};
+
+// Immutables.org value type: an abstract interface with one accessor per
+// field; the annotation processor generates the `Immutable*` implementation.
+pub(crate) fn render_immutable_interface(
+    scope: &TypeScope,
+    m: &Message,
+    opts: &JavaGenOptions,
+) -> String {
+    let mut imports = vec!["org.immutables.value.Value"];
+    if any_field(scope.model, m, opts, is_repeated) {
+        imports.push("java.util.List");
+    }
+    if any_field(scope.model, m, opts, is_map) {
+        imports.push("java.util.Map");
+    }
+    imports.extend(nested_enum_imports(scope.model, m, opts));
+    let mut type_imports = Vec::new();
+    collect_type_imports(scope, m, opts, &mut type_imports);
+    imports.extend(type_imports.iter().map(String::as_str));
+
+    let mut s = String::new();
+    s.push_str(&render_package_line(scope.java_package().as_deref()));
+    s.push_str(&render_imports(&imports));
+    s.push_str(&render_interface_decl(scope, m, opts));
+    s
+}
+
+// Whether `m`, or a nested message rendered inside it, has a matching field.
+pub(crate) fn any_field(
+    model: &ProtoModel,
+    m: &Message,
+    opts: &JavaGenOptions,
+    pred: fn(&Field) -> bool,
+) -> bool {
+    m.fields.iter().any(pred)
+        || (!opts.flatten_nested
+            && model.nested_types(&m.name).any(|t| match t {
+                TypeDecl::Message(nested) => any_field(model, nested, opts, pred),
+                TypeDecl::Enum(_) => false,
+            }))
+}
+
+// Nested types become member interfaces/enums, which are implicitly static.
+fn render_interface_decl(scope: &TypeScope, m: &Message, opts: &JavaGenOptions) -> String {
+    let mut s = checkstyle::doc(opts, "", &format!("Message {{@code {}}}.", m.name));
+    s.push_str("@Value.Immutable\n");
+    s.push_str(&format!(
+        "public interface {} {{\n",
+        java_class_name(&m.name, opts)
+    ));
+    for f in &m.fields {
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            &format!("Returns {}.\n\n@return the value", checkstyle::field_ref(f)),
+        ));
+        s.push_str(&format!(
+            "    {} {}();\n",
+            java_field_type(f, scope),
+            scope.member(f)
+        ));
+    }
+    if !opts.flatten_nested {
+        for t in scope.model.nested_types(&m.name) {
+            let decl = match t {
+                TypeDecl::Message(nested) => {
+                    let nested_scope = TypeScope {
+                        message: &nested.name,
+                        ..*scope
+                    };
+                    render_interface_decl(&nested_scope, nested, opts)
+                }
+                TypeDecl::Enum(e) => render_enum_decl(e, opts),
+            };
+            s.push('\n');
+            s.push_str(&indent(&decl));
+        }
+    }
+    s.push_str("}\n");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::JavaGenOptions;
+    use crate::tests::gen_file;
+
+    #[test]
+    fn renders_value_immutable_interface() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+                int32 id = 1;
+                string name = 2;
+                repeated Item items = 3;
+            }
+            message Item { string sku = 1; }
+        "#;
+        let opts = JavaGenOptions {
+            immutables: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "shop/Order.java");
+        assert!(src.contains("import java.util.List;\nimport org.immutables.value.Value;\n"));
+        assert!(src.contains("@Value.Immutable\npublic interface Order {\n"));
+        assert!(src.contains("    int id();\n"));
+        assert!(src.contains("    String name();\n"));
+        assert!(src.contains("    List<Item> items();\n"));
+        assert!(!src.contains("private "));
+    }
+}
@@ -1,16 +1,307 @@
+mod checkstyle;
+mod immutables;
+mod lite;
+mod proto_bridge;
+mod registry;
+mod service;
+
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use parser::{FieldType, ProtoModel, ScalarType, TypeDecl, parse_proto_file};
+use parser::{
+    Field, FieldLabel, FieldType, OptionValue, ProtoModel, ProtoSyntax, ScalarType, TypeDecl,
+    ValidationError, parse_proto_file, validate,
+};
+
+pub use registry::{JavaTypeRef, TypeRegistry};
+
+/// A generated file: (relative_file_path, file_content).
+pub type GeneratedFile = (String, String);
+
+/// Options controlling the shape of the generated Java code.
+#[derive(Debug, Clone)]
+pub struct JavaGenOptions {
+    /// Initialize proto3 implicit-presence scalar fields to their proto defaults
+    /// (0, false, "") instead of leaving them at Java defaults.
+    pub proto3_implicit_defaults: bool,
+    /// Generate a static nested `Builder` class and a `newBuilder()` factory.
+    pub builder: bool,
+    /// Emit the public no-arg constructor (default true).
+    pub no_arg_constructor: bool,
+    /// Emit a constructor taking every field in declaration order.
+    pub all_args_constructor: bool,
+    /// Fully-qualified generic type wrapping streamed rpc inputs/outputs in
+    /// service interfaces (default `java.util.stream.Stream`).
+    pub stream_type: String,
+    /// Annotate fields with Gson's `@SerializedName` carrying the JSON key.
+    pub gson: bool,
+    /// Annotate fields with Jackson's `@JsonProperty` carrying the JSON key.
+    pub jackson: bool,
+    /// Emit `public static final int <NAME>_FIELD_NUMBER = <tag>;` per field.
+    pub tag_constants: bool,
+    /// License/banner text emitted as a comment block at the top of every file.
+    pub file_header: Option<String>,
+    /// Annotate top-level types with `@javax.annotation.processing.Generated`.
+    pub generated_annotation: bool,
+    /// Emit nested messages/enums as top-level types named by joining the
+    /// nesting path (`Order.Status` becomes `OrderStatus`).
+    pub flatten_nested: bool,
+    /// Track presence of optional/required fields in a private bitset with
+    /// `hasX()`/`clearX()` accessors, instead of relying on null checks.
+    pub presence_bitset: bool,
+    /// Emit messages as Immutables.org `@Value.Immutable` interfaces with
+    /// accessor-style methods instead of mutable POJOs.
+    pub immutables: bool,
+    /// Fully-qualified annotation type placed on every field with the proto
+    /// tag as its value, e.g. `@FieldNumber(3)`, for numeric-keyed serializers.
+    pub field_number_annotation: Option<String>,
+    /// Annotate classes and fields for XML Binding (JAXB), using the proto
+    /// field name as the element name.
+    pub jaxb: bool,
+    /// Emit fields, accessors and constructor parameters in alphabetical
+    /// field-name order instead of declaration order.
+    pub sort_members: bool,
+    /// Getters of message-typed and `optional` fields return `Optional<T>`
+    /// (`OptionalInt`/`OptionalLong`/`OptionalDouble` for primitives), with
+    /// optional primitives stored boxed so that null means unset.
+    pub optional_getters: bool,
+    /// Emit a static `FIELD_ACCESSORS` map from proto field name to getter,
+    /// for reflection-free generic field access.
+    pub field_accessor_map: bool,
+    /// How a generated type's name maps to its file name (without `.java`).
+    /// javac requires a public top-level type to live in a file of the same
+    /// name, so non-default strategies suit post-processing pipelines.
+    pub file_naming: FileNaming,
+    /// Prepended to every generated message/enum class name, e.g. `Proto`.
+    pub class_prefix: String,
+    /// Appended to every generated message/enum class name, e.g. `Dto` makes
+    /// `Order` -> `OrderDto`. References between generated types follow.
+    pub class_suffix: String,
+    /// Which kinds of types to emit, for partial regeneration (e.g. when enums
+    /// live in a separate module). Services are only emitted with `All`.
+    pub type_filter: TypeFilter,
+    /// Generate `toProto()`/`fromProto(..)` methods converting to and from
+    /// parallel protobuf-java classes, for teams using protobuf-java on the
+    /// wire and the POJOs in their domain. Needs the no-arg constructor.
+    pub proto_bridge: Option<ProtoBridge>,
+    /// How types from another proto package are referenced.
+    pub reference_style: ReferenceStyle,
+    /// Annotate fields with Bean Validation (JSR-380) constraints: `@NotNull`
+    /// on required fields, plus `@Size`, `@Min`, `@Max`, `@Pattern` and
+    /// `@Email` from protoc-gen-validate `(validate.rules)` options.
+    pub bean_validation: bool,
+    /// Emit an `isX()` predicate per enum constant (`PAID_IN_FULL` ->
+    /// `isPaidInFull()`) for readable conditionals.
+    pub enum_predicates: bool,
+    /// Proto package prefix -> Java package prefix rules, e.g.
+    /// `("me.alekseinovikov.proto", "com.acme.generated")`, applied to package
+    /// lines, output directories and cross-package references. Prefixes match
+    /// whole segments and the longest matching prefix wins.
+    pub package_mapping: Vec<(String, String)>,
+    /// Order the output so referenced types come before the types using them.
+    /// Types in a reference cycle keep their declaration order.
+    pub topo_order: bool,
+    /// Append an `UNRECOGNIZED(-1)` constant to every enum, as protobuf-java
+    /// does, plus a static `fromNumber(int)` returning it for unknown numbers.
+    pub unrecognized_enum_value: bool,
+    /// Shape the output to pass a default Checkstyle configuration: camelCase
+    /// member names (JSON keys keep the proto name), `final` parameters,
+    /// Javadoc on every type and member, one statement per line and a single
+    /// blank line between methods. A field whose camelCase name would be a
+    /// Java keyword or another field's name keeps its proto name.
+    pub checkstyle_compatible: bool,
+    /// When writing to disk, also write `generated-files.txt` (see
+    /// [`MANIFEST_FILE`]) in the output root listing each generated file,
+    /// for clean and incremental builds.
+    pub manifest: bool,
+    /// Declared message name (`Order`, `Order.Line`) -> base class the
+    /// generated class `extends`, e.g. `com.acme.AbstractEnvelope`.
+    pub base_class_for: HashMap<String, String>,
+    /// Map well-known types to plain Java: `google.protobuf.Timestamp` to
+    /// `java.time.Instant`, `Duration` to `java.time.Duration`, and the
+    /// wrapper types (`Int32Value`, `StringValue`, ...) to their boxed,
+    /// nullable Java counterparts.
+    pub java_time: bool,
+    /// Package family for the validation and XML binding annotations.
+    pub annotation_namespace: AnnotationNamespace,
+    /// With `builder`, make `build()` throw `IllegalStateException` when a
+    /// proto2 `required` field was never set. Required fields then keep a
+    /// presence bit (see `presence_bitset`), so `toBuilder()` of a message
+    /// missing one still fails.
+    pub validate_on_build: bool,
+    /// Getters of singular message-typed fields throw `IllegalStateException`
+    /// when the field is null; check with the accompanying `hasX()` first.
+    pub strict_getters: bool,
+    /// Give every enum a static `BY_NUMBER` map, filled in a static
+    /// initializer, and a `fromNumber(int)` looking numbers up in it
+    /// (instead of the switch `unrecognized_enum_value` uses on its own).
+    pub enum_number_map: bool,
+    /// Emit a `withX(value)` per field returning a copy of the message with
+    /// that one field replaced, for code treating messages as immutable.
+    pub withers: bool,
+    /// Initialize every field that has no null-means-unset presence to its
+    /// proto default: numbers to zero, `false`, `""`, an empty `byte[]`, and
+    /// repeated and map fields to an empty collection. Message and enum
+    /// fields stay null.
+    pub explicit_defaults: bool,
+    /// Emit minimal Protobuf-lite style classes instead of full POJOs:
+    /// public final fields, one all-args constructor and no other methods.
+    pub lite: bool,
+}
+
+/// `javax.*` (Java EE 8 and earlier) or `jakarta.*` (Jakarta EE 9+)
+/// annotation packages. `@Generated` is the JDK's own
+/// `javax.annotation.processing.Generated` under either, as it needs no
+/// extra dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnnotationNamespace {
+    /// `javax.validation` and `javax.xml.bind`.
+    Javax,
+    /// `jakarta.validation` and `jakarta.xml.bind`.
+    #[default]
+    Jakarta,
+}
+
+impl AnnotationNamespace {
+    fn xml_binding_import(&self) -> &'static str {
+        match self {
+            AnnotationNamespace::Javax => "javax.xml.bind.annotation.*",
+            AnnotationNamespace::Jakarta => "jakarta.xml.bind.annotation.*",
+        }
+    }
+
+    fn validation_import(&self) -> &'static str {
+        match self {
+            AnnotationNamespace::Javax => "javax.validation.constraints.*",
+            AnnotationNamespace::Jakarta => "jakarta.validation.constraints.*",
+        }
+    }
+}
+
+/// Reference style for types declared in another package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferenceStyle {
+    /// Inline fully-qualified name: `shop.common.Money`.
+    #[default]
+    FullyQualified,
+    /// `import shop.common.Money;` plus the simple name, falling back to the
+    /// qualified name where it would clash with a type of this package.
+    Import,
+}
+
+/// Where the protobuf-java classes bridged by `toProto()`/`fromProto(..)` live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtoBridge {
+    /// Java package of the protobuf-java classes, e.g. `com.acme`.
+    pub package: String,
+    /// Appended to top-level type names: `Proto` maps `Order` to
+    /// `com.acme.OrderProto` and `Order.Address` to `com.acme.OrderProto.Address`.
+    pub class_suffix: String,
+}
+
+/// Kinds of top-level types `generate_java` emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeFilter {
+    #[default]
+    All,
+    MessagesOnly,
+    /// Enums nested in a message are emitted with it, so this only sees them
+    /// together with `flatten_nested`.
+    EnumsOnly,
+}
+
+impl TypeFilter {
+    pub fn includes(&self, t: &TypeDecl) -> bool {
+        matches!(
+            (self, t),
+            (TypeFilter::All, _)
+                | (TypeFilter::MessagesOnly, TypeDecl::Message(_))
+                | (TypeFilter::EnumsOnly, TypeDecl::Enum(_))
+        )
+    }
+}
+
+/// File naming strategy for generated sources.
+#[derive(Debug, Clone, Default)]
+pub enum FileNaming {
+    /// The Java type name: `Order` -> `Order.java`.
+    #[default]
+    TypeName,
+    /// Type name plus a fixed suffix: `Dto` makes `Order` -> `OrderDto.java`.
+    Suffix(String),
+    /// Any mapping from the type name to the file stem.
+    Custom(fn(&str) -> String),
+}
+
+impl FileNaming {
+    pub fn file_name(&self, type_name: &str) -> String {
+        let stem = match self {
+            FileNaming::TypeName => type_name.to_string(),
+            FileNaming::Suffix(suffix) => format!("{}{}", type_name, suffix),
+            FileNaming::Custom(f) => f(type_name),
+        };
+        format!("{}.java", stem)
+    }
+}
+
+impl Default for JavaGenOptions {
+    fn default() -> Self {
+        Self {
+            proto3_implicit_defaults: false,
+            builder: false,
+            no_arg_constructor: true,
+            all_args_constructor: false,
+            stream_type: "java.util.stream.Stream".to_string(),
+            gson: false,
+            jackson: false,
+            tag_constants: false,
+            file_header: None,
+            generated_annotation: false,
+            flatten_nested: false,
+            presence_bitset: false,
+            immutables: false,
+            field_number_annotation: None,
+            jaxb: false,
+            sort_members: false,
+            optional_getters: false,
+            field_accessor_map: false,
+            file_naming: FileNaming::TypeName,
+            class_prefix: String::new(),
+            class_suffix: String::new(),
+            type_filter: TypeFilter::All,
+            proto_bridge: None,
+            reference_style: ReferenceStyle::FullyQualified,
+            bean_validation: false,
+            enum_predicates: false,
+            package_mapping: Vec::new(),
+            topo_order: false,
+            unrecognized_enum_value: false,
+            checkstyle_compatible: false,
+            manifest: false,
+            base_class_for: HashMap::new(),
+            java_time: false,
+            annotation_namespace: AnnotationNamespace::Jakarta,
+            validate_on_build: false,
+            strict_getters: false,
+            enum_number_map: false,
+            withers: false,
+            explicit_defaults: false,
+            lite: false,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum GenerateError {
     Parse(parser::ParseError),
+    Io(std::io::Error),
 }
 
 impl std::fmt::Display for GenerateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             GenerateError::Parse(e) => write!(f, "parse error: {}", e),
+            GenerateError::Io(e) => write!(f, "io error: {}", e),
         }
     }
 }
@@ -23,26 +314,108 @@ impl From<parser::ParseError> for GenerateError {
     }
 }
 
+impl From<std::io::Error> for GenerateError {
+    fn from(e: std::io::Error) -> Self {
+        GenerateError::Io(e)
+    }
+}
+
 /// Generate Java sources from a .proto file path.
 /// Returns a list of tuples: (relative_file_path, file_content).
 /// The relative_file_path uses '/' separators and includes package directories if present.
 pub fn generate_java_from_proto<P: AsRef<Path>>(
     proto_path: P,
-) -> Result<Vec<(String, String)>, GenerateError> {
+) -> Result<Vec<GeneratedFile>, GenerateError> {
     let model = parse_proto_file(proto_path)?;
     Ok(generate_java_from_model(&model))
 }
 
 /// Internal: generate Java source files from the ProtoModel
-pub fn generate_java_from_model(model: &ProtoModel) -> Vec<(String, String)> {
-    let pkg_path = model.package.as_ref().map(|p| p.replace('.', "/"));
+pub fn generate_java_from_model(model: &ProtoModel) -> Vec<GeneratedFile> {
+    generate_java_with_options(model, &JavaGenOptions::default())
+}
+
+/// Generate Java source files from the ProtoModel using the given options.
+pub fn generate_java_with_options(model: &ProtoModel, opts: &JavaGenOptions) -> Vec<GeneratedFile> {
+    generate_java(model, opts, None)
+}
+
+/// Validate the model first and only generate when it is valid.
+/// Use this for untrusted input; the infallible functions assume a valid model.
+pub fn generate_java_checked(
+    model: &ProtoModel,
+    opts: &JavaGenOptions,
+) -> Result<Vec<GeneratedFile>, Vec<ValidationError>> {
+    validate(model)?;
+    Ok(generate_java_with_options(model, opts))
+}
+
+/// Generate Java source files, resolving custom types through `registry` so
+/// types from other packages are referenced by their fully-qualified name.
+pub fn generate_java_with_registry(
+    model: &ProtoModel,
+    opts: &JavaGenOptions,
+    registry: &TypeRegistry,
+) -> Vec<GeneratedFile> {
+    generate_java(model, opts, Some(registry))
+}
+
+fn generate_java(
+    model: &ProtoModel,
+    opts: &JavaGenOptions,
+    registry: Option<&TypeRegistry>,
+) -> Vec<GeneratedFile> {
+    // Synthetic map-entry messages (`option map_entry = true`) are never
+    // emitted, neither as files nor as member classes
+    let is_map_entry = |t: &TypeDecl| matches!(t, TypeDecl::Message(m) if m.is_map_entry());
+    let without_entries;
+    let model = if model.types.iter().any(is_map_entry) {
+        let mut copy = model.clone();
+        copy.types.retain(|t| !is_map_entry(t));
+        without_entries = copy;
+        &without_entries
+    } else {
+        model
+    };
+    let java_package = map_package(&opts.package_mapping, model.package.as_deref());
+    let pkg_path = java_package.as_ref().map(|p| p.replace('.', "/"));
 
     let mut out = Vec::new();
-    for t in &model.types {
+    // Nested types are emitted inside their parent unless flattened
+    let mut emitted: Vec<&TypeDecl> = model
+        .types
+        .iter()
+        .filter(|t| opts.flatten_nested || !t.name().contains('.'))
+        .filter(|t| opts.type_filter.includes(t))
+        .collect();
+    if opts.topo_order {
+        emitted = topo_sorted(model, emitted);
+    }
+    for t in emitted {
         match t {
             TypeDecl::Message(m) => {
-                let code = render_message_class(model.package.as_deref(), m);
-                let file_name = format!("{}.java", m.name);
+                let scope = TypeScope {
+                    package: model.package.as_deref(),
+                    message: &m.name,
+                    model,
+                    registry,
+                    flatten_nested: opts.flatten_nested,
+                    optional_getters: opts.optional_getters,
+                    class_prefix: &opts.class_prefix,
+                    class_suffix: &opts.class_suffix,
+                    reference_style: opts.reference_style,
+                    package_mapping: &opts.package_mapping,
+                    java_time: opts.java_time,
+                    camel_case_members: opts.checkstyle_compatible,
+                };
+                let code = if opts.immutables {
+                    immutables::render_immutable_interface(&scope, m, opts)
+                } else if opts.lite {
+                    lite::render_lite_class(&scope, m, opts)
+                } else {
+                    render_message_class(&scope, m, opts)
+                };
+                let file_name = opts.file_naming.file_name(&java_class_name(&m.name, opts));
                 let rel = if let Some(ref pp) = pkg_path {
                     PathBuf::from(pp).join(&file_name)
                 } else {
@@ -51,8 +424,8 @@ pub fn generate_java_from_model(model: &ProtoModel) -> Vec<(String, String)> {
                 out.push((rel_to_string(&rel), code));
             }
             TypeDecl::Enum(e) => {
-                let code = render_enum(model.package.as_deref(), e);
-                let file_name = format!("{}.java", e.name);
+                let code = render_enum(java_package.as_deref(), e, opts);
+                let file_name = opts.file_naming.file_name(&java_class_name(&e.name, opts));
                 let rel = if let Some(ref pp) = pkg_path {
                     PathBuf::from(pp).join(&file_name)
                 } else {
@@ -62,14 +435,275 @@ pub fn generate_java_from_model(model: &ProtoModel) -> Vec<(String, String)> {
             }
         }
     }
+    let services = match opts.type_filter {
+        TypeFilter::All => &model.services[..],
+        _ => &[],
+    };
+    for svc in services {
+        let scope = TypeScope {
+            package: model.package.as_deref(),
+            message: "",
+            model,
+            registry,
+            flatten_nested: opts.flatten_nested,
+            optional_getters: opts.optional_getters,
+            class_prefix: &opts.class_prefix,
+            class_suffix: &opts.class_suffix,
+            reference_style: opts.reference_style,
+            package_mapping: &opts.package_mapping,
+            java_time: opts.java_time,
+            camel_case_members: opts.checkstyle_compatible,
+        };
+        let code = service::render_service_interface(&scope, svc, opts);
+        let file_name = opts.file_naming.file_name(&svc.name);
+        let rel = if let Some(ref pp) = pkg_path {
+            PathBuf::from(pp).join(&file_name)
+        } else {
+            PathBuf::from(&file_name)
+        };
+        out.push((rel_to_string(&rel), code));
+    }
+
+    if opts.checkstyle_compatible {
+        for (_, code) in &mut out {
+            *code = checkstyle::layout(code);
+        }
+    }
+    for (_, code) in &mut out {
+        decorate_file(code, opts);
+    }
     out
 }
 
+// Dependencies-first order of the emitted types. A type depends on whatever
+// its fields (or those of its member classes) reference; a reference cycle is
+// emitted as one block in declaration order once its other dependencies are.
+fn topo_sorted<'a>(model: &ProtoModel, units: Vec<&'a TypeDecl>) -> Vec<&'a TypeDecl> {
+    // the emitted type a (possibly nested) type name lives in
+    let unit_of = |name: &str| {
+        let mut name = name;
+        loop {
+            if let Some(i) = units.iter().position(|t| t.name() == name) {
+                return Some(i);
+            }
+            name = name.rsplit_once('.')?.0;
+        }
+    };
+    let n = units.len();
+    let mut edges = vec![Vec::new(); n];
+    for t in &model.types {
+        let TypeDecl::Message(m) = t else { continue };
+        let Some(i) = unit_of(&m.name) else { continue };
+        for dep in m.dependencies() {
+            let Some(j) = model
+                .resolve_type_name(&m.name, &dep)
+                .and_then(|local| unit_of(&local))
+            else {
+                continue;
+            };
+            if j != i && !edges[i].contains(&j) {
+                edges[i].push(j);
+            }
+        }
+    }
+    let reach: Vec<Vec<bool>> = (0..n)
+        .map(|i| {
+            let mut seen = vec![false; n];
+            let mut stack = vec![i];
+            while let Some(k) = stack.pop() {
+                for &j in &edges[k] {
+                    if !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+            seen
+        })
+        .collect();
+    let same_cycle = |i: usize, j: usize| i == j || (reach[i][j] && reach[j][i]);
+
+    let mut done = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    while order.len() < n {
+        let ready = |i: usize| {
+            (0..n)
+                .filter(|&k| same_cycle(i, k))
+                .all(|k| edges[k].iter().all(|&j| done[j] || same_cycle(i, j)))
+        };
+        let next = (0..n)
+            .find(|&i| !done[i] && ready(i))
+            .expect("cycles are collapsed, so some type is always ready");
+        for j in (0..n).filter(|&j| same_cycle(next, j)) {
+            done[j] = true;
+            order.push(units[j]);
+        }
+    }
+    order
+}
+
+const GENERATED_MARKER: &str = "// Code generated by rs-proto-to-pojo; DO NOT EDIT.\n";
+
+// File-level decorations shared by every generated file: optional banner,
+// the generated-code marker and the optional @Generated annotation.
+fn decorate_file(code: &mut String, opts: &JavaGenOptions) {
+    if opts.generated_annotation {
+        // top-level declaration: first line starting with `public `
+        let idx = if code.starts_with("public ") {
+            Some(0)
+        } else {
+            code.find("\npublic ").map(|i| i + 1)
+        };
+        if let Some(idx) = idx {
+            code.insert_str(
+                idx,
+                "@javax.annotation.processing.Generated(\"rs-proto-to-pojo\")\n",
+            );
+        }
+    }
+    code.insert_str(0, GENERATED_MARKER);
+    if let Some(header) = &opts.file_header {
+        code.insert_str(0, &render_file_header(header));
+    }
+}
+
+/// Name of the manifest written with `JavaGenOptions::manifest`: one line per
+/// generated file, its path relative to the output root, followed by a tab
+/// and the source proto when known (relative to the input directory).
+pub const MANIFEST_FILE: &str = "generated-files.txt";
+
+/// Generate Java for the model and write each file under `out_dir`,
+/// creating package directories as needed. Returns the written paths
+/// (the manifest, if any, is not included).
+pub fn write_java_to_dir<P: AsRef<Path>>(
+    model: &ProtoModel,
+    out_dir: P,
+    opts: &JavaGenOptions,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let files = generate_java_with_options(model, opts);
+    let mut manifest = String::new();
+    push_manifest(&mut manifest, &files, None);
+    let written = write_files(out_dir.as_ref(), files)?;
+    if opts.manifest {
+        std::fs::write(out_dir.as_ref().join(MANIFEST_FILE), manifest)?;
+    }
+    Ok(written)
+}
+
+/// Like [`write_java_to_dir`], but files whose content on disk is already
+/// up to date are left untouched, so their mtimes don't trigger downstream
+/// rebuilds. Returns only the paths that were (re)written.
+pub fn write_java_to_dir_incremental<P: AsRef<Path>>(
+    model: &ProtoModel,
+    out_dir: P,
+    opts: &JavaGenOptions,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let out_dir = out_dir.as_ref();
+    let files = generate_java_with_options(model, opts);
+    let mut manifest = String::new();
+    push_manifest(&mut manifest, &files, None);
+    let changed: Vec<GeneratedFile> = files
+        .into_iter()
+        .filter(|(rel, code)| !is_up_to_date(&out_dir.join(rel), code))
+        .collect();
+    let written = write_files(out_dir, changed)?;
+    let manifest_path = out_dir.join(MANIFEST_FILE);
+    if opts.manifest && !is_up_to_date(&manifest_path, &manifest) {
+        std::fs::write(manifest_path, manifest)?;
+    }
+    Ok(written)
+}
+
+fn is_up_to_date(path: &Path, content: &str) -> bool {
+    std::fs::read(path).is_ok_and(|existing| existing == content.as_bytes())
+}
+
+/// Generate Java for every `*.proto` under `dir` (recursively) into `out_dir`.
+/// All files are parsed first so types imported from sibling files resolve
+/// to their own Java packages. Non-proto files are ignored.
+pub fn generate_java_from_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    dir: P,
+    out_dir: Q,
+    opts: &JavaGenOptions,
+) -> Result<Vec<PathBuf>, GenerateError> {
+    let mut protos = Vec::new();
+    collect_proto_files(dir.as_ref(), &mut protos)?;
+    protos.sort();
+
+    let mut models = Vec::new();
+    for path in &protos {
+        models.push(parse_proto_file(path)?);
+    }
+    let registry = TypeRegistry::from_models(&models);
+
+    let mut written = Vec::new();
+    let mut manifest = String::new();
+    for (model, path) in models.iter().zip(&protos) {
+        let files = generate_java_with_registry(model, opts, &registry);
+        let source = path.strip_prefix(dir.as_ref()).unwrap_or(path);
+        push_manifest(&mut manifest, &files, Some(source));
+        written.extend(write_files(out_dir.as_ref(), files)?);
+    }
+    if opts.manifest {
+        std::fs::write(out_dir.as_ref().join(MANIFEST_FILE), manifest)?;
+    }
+    Ok(written)
+}
+
+fn push_manifest(manifest: &mut String, files: &[GeneratedFile], source: Option<&Path>) {
+    for (rel, _) in files {
+        manifest.push_str(rel);
+        if let Some(source) = source {
+            manifest.push('\t');
+            manifest.push_str(&rel_to_string(source));
+        }
+        manifest.push('\n');
+    }
+}
+
+fn collect_proto_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_proto_files(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "proto") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn write_files(out_dir: &Path, files: Vec<GeneratedFile>) -> Result<Vec<PathBuf>, GenerateError> {
+    let mut written = Vec::new();
+    for (rel, code) in files {
+        let path = out_dir.join(rel);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, code)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
 fn rel_to_string(p: &Path) -> String {
     let s = p.to_string_lossy().to_string();
     s.replace('\\', "/")
 }
 
+fn render_file_header(header: &str) -> String {
+    let mut s = String::from("/*\n");
+    for line in header.lines() {
+        if line.is_empty() {
+            s.push_str(" *\n");
+        } else {
+            s.push_str(&format!(" * {}\n", line));
+        }
+    }
+    s.push_str(" */\n");
+    s
+}
+
 fn render_package_line(pkg: Option<&str>) -> String {
     match pkg {
         Some(p) if !p.is_empty() => format!("package {};\n\n", p),
@@ -77,101 +711,2723 @@ fn render_package_line(pkg: Option<&str>) -> String {
     }
 }
 
-fn render_enum(pkg: Option<&str>, e: &parser::Enum) -> String {
-    let mut s = String::new();
-    s.push_str(&render_package_line(pkg));
-    s.push_str(&format!("public enum {} {{\n", e.name));
+fn render_trailing_doc(doc: Option<&str>) -> String {
+    match doc {
+        Some(d) => format!(" // {}\n", d),
+        None => "\n".to_string(),
+    }
+}
+
+fn render_enum(pkg: Option<&str>, e: &parser::Enum, opts: &JavaGenOptions) -> String {
+    let imports: &[&str] = if opts.enum_number_map {
+        &ENUM_NUMBER_MAP_IMPORTS
+    } else {
+        &[]
+    };
+    render_package_line(pkg) + &render_imports(imports) + &render_enum_decl(e, opts)
+}
+
+const ENUM_NUMBER_MAP_IMPORTS: [&str; 2] = ["java.util.HashMap", "java.util.Map"];
+
+// Imports for the `BY_NUMBER` maps of enums nested (at any depth) in `m`.
+fn nested_enum_imports(
+    model: &ProtoModel,
+    m: &parser::Message,
+    opts: &JavaGenOptions,
+) -> &'static [&'static str] {
+    let prefix = format!("{}.", m.name);
+    let has_nested_enum = model
+        .types
+        .iter()
+        .any(|t| matches!(t, TypeDecl::Enum(e) if e.name.starts_with(&prefix)));
+    if opts.enum_number_map && !opts.flatten_nested && has_nested_enum {
+        &ENUM_NUMBER_MAP_IMPORTS
+    } else {
+        &[]
+    }
+}
+
+fn render_enum_decl(e: &parser::Enum, opts: &JavaGenOptions) -> String {
+    let class = java_class_name(&e.name, opts);
+    let mut s = checkstyle::doc(opts, "", &format!("Enum {{@code {}}}.", e.name));
+    s.push_str(&format!("public enum {} {{\n", class));
     for (idx, v) in e.values.iter().enumerate() {
-        let sep = if idx + 1 == e.values.len() { ";" } else { "," };
-        s.push_str(&format!("    {}({}){}\n", v.name, v.number, sep));
+        let last = idx + 1 == e.values.len() && !opts.unrecognized_enum_value;
+        let sep = if last { ";" } else { "," };
+        match &v.leading_doc {
+            Some(doc) => s.push_str(&render_javadoc(doc, "    ")),
+            None => s.push_str(&checkstyle::doc(
+                opts,
+                "    ",
+                &format!("{{@code {}}} = {}.", v.name, v.number),
+            )),
+        }
+        if v.is_deprecated() {
+            s.push_str("    @Deprecated\n");
+        }
+        s.push_str(&format!("    {}({}){}", v.name, v.number, sep));
+        s.push_str(&render_trailing_doc(v.trailing_doc.as_deref()));
+    }
+    if opts.unrecognized_enum_value {
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            "A number this enum does not declare.",
+        ));
+        s.push_str("    UNRECOGNIZED(-1);\n");
     }
-    s.push_str("\n    private final int number;\n");
+    s.push('\n');
+    s.push_str(&checkstyle::doc(opts, "    ", "The value's number."));
+    s.push_str("    private final int number;\n");
     s.push_str(&format!(
-        "    {}(int number) {{ this.number = number; }}\n",
-        e.name
+        "    {}({}) {{ this.number = number; }}\n",
+        class,
+        checkstyle::param(opts, "int", "number")
+    ));
+    s.push_str(&checkstyle::doc(
+        opts,
+        "    ",
+        "Returns the value's number.\n\n@return the number",
     ));
     s.push_str("    public int getNumber() { return number; }\n");
+    let from_number_doc = checkstyle::doc(
+        opts,
+        "    ",
+        "Returns the value with the given number.\n\n@param number the number to look up\n@return the value",
+    );
+    let number = checkstyle::param(opts, "int", "number");
+    if opts.enum_number_map {
+        // putIfAbsent keeps the first of several aliases
+        s.push('\n');
+        s.push_str(&checkstyle::doc(opts, "    ", "Values by number."));
+        s.push_str(&format!(
+            "    private static final Map<Integer, {}> BY_NUMBER = new HashMap<>();\n",
+            class
+        ));
+        s.push_str("    static {\n");
+        s.push_str(&format!("        for ({} value : values()) {{\n", class));
+        if opts.unrecognized_enum_value {
+            s.push_str("            if (value == UNRECOGNIZED) continue;\n");
+        }
+        s.push_str("            BY_NUMBER.putIfAbsent(value.number, value);\n");
+        s.push_str("        }\n    }\n\n");
+        let lookup = if opts.unrecognized_enum_value {
+            "BY_NUMBER.getOrDefault(number, UNRECOGNIZED)"
+        } else {
+            "BY_NUMBER.get(number)"
+        };
+        s.push_str(&from_number_doc);
+        s.push_str(&format!(
+            "    public static {} fromNumber({}) {{ return {}; }}\n",
+            class, number, lookup
+        ));
+    } else if opts.unrecognized_enum_value {
+        s.push('\n');
+        s.push_str(&from_number_doc);
+        s.push_str(&format!(
+            "    public static {} fromNumber({}) {{\n        switch (number) {{\n",
+            class, number
+        ));
+        let mut seen = Vec::new();
+        // aliases share a number; the first constant wins, as in protobuf-java
+        for v in e.values.iter().filter(|v| {
+            let first = !seen.contains(&v.number);
+            seen.push(v.number);
+            first
+        }) {
+            s.push_str(&format!(
+                "            case {}: return {};\n",
+                v.number, v.name
+            ));
+        }
+        s.push_str("            default: return UNRECOGNIZED;\n        }\n    }\n");
+    }
+    if opts.enum_predicates {
+        s.push('\n');
+        for v in &e.values {
+            s.push_str(&checkstyle::doc(
+                opts,
+                "    ",
+                &format!(
+                    "Returns whether this is {{@code {}}}.\n\n@return {{@code true}} if it is",
+                    v.name
+                ),
+            ));
+            s.push_str(&format!(
+                "    public boolean is{}() {{ return this == {}; }}\n",
+                upper_camel(&v.name),
+                v.name
+            ));
+        }
+    }
     s.push_str("}\n");
     s
 }
 
-fn render_message_class(pkg: Option<&str>, m: &parser::Message) -> String {
+fn render_javadoc(doc: &str, pad: &str) -> String {
+    let doc = doc.replace("*/", "*&#47;");
+    if !doc.contains('\n') {
+        return format!("{}/** {} */\n", pad, doc);
+    }
+    let mut s = format!("{}/**\n", pad);
+    for line in doc.lines() {
+        if line.is_empty() {
+            s.push_str(&format!("{} *\n", pad));
+        } else {
+            s.push_str(&format!("{} * {}\n", pad, line));
+        }
+    }
+    s.push_str(&format!("{} */\n", pad));
+    s
+}
+
+fn render_imports(imports: &[&str]) -> String {
+    if imports.is_empty() {
+        return String::new();
+    }
+    let mut sorted = imports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut s = String::new();
+    for i in sorted {
+        s.push_str(&format!("import {};\n", i));
+    }
+    s.push('\n');
+    s
+}
+
+fn render_message_class(scope: &TypeScope, m: &parser::Message, opts: &JavaGenOptions) -> String {
+    let mut imports = Vec::new();
+    collect_message_imports(scope, m, opts, &mut imports);
+    let mut type_imports = Vec::new();
+    collect_type_imports(scope, m, opts, &mut type_imports);
+    imports.extend(type_imports.iter().map(String::as_str));
+    let mut s = String::new();
+    s.push_str(&render_package_line(scope.java_package().as_deref()));
+    s.push_str(&render_imports(&imports));
+    s.push_str(&render_message_decl(scope, m, opts));
+    s
+}
+
+// Imports of cross-package field types of a message and, unless flattened,
+// its nested messages.
+fn collect_type_imports(
+    scope: &TypeScope,
+    m: &parser::Message,
+    opts: &JavaGenOptions,
+    imports: &mut Vec<String>,
+) {
+    for f in &m.fields {
+        if let Some(name) = f.ty.type_name()
+            && let Some(import) = type_import(name, scope)
+        {
+            imports.push(import);
+        }
+    }
+    if !opts.flatten_nested {
+        for t in scope.model.nested_types(&m.name) {
+            if let TypeDecl::Message(nested) = t {
+                let nested_scope = TypeScope {
+                    message: &nested.name,
+                    ..*scope
+                };
+                collect_type_imports(&nested_scope, nested, opts, imports);
+            }
+        }
+    }
+}
+
+// Imports needed by a message and, unless flattened, its nested messages.
+fn collect_message_imports<'a>(
+    scope: &TypeScope,
+    m: &parser::Message,
+    opts: &'a JavaGenOptions,
+    imports: &mut Vec<&'a str>,
+) {
+    let has_repeated = m.fields.iter().any(is_repeated);
+    if has_repeated && (opts.builder || opts.proto_bridge.is_some() || opts.explicit_defaults) {
+        imports.push("java.util.ArrayList");
+    }
+    if has_repeated {
+        imports.push("java.util.List");
+    }
+    let has_map = m.fields.iter().any(is_map);
+    if has_map && (opts.proto_bridge.is_some() || opts.explicit_defaults) {
+        imports.push("java.util.HashMap");
+    }
+    if has_map {
+        imports.push("java.util.Map");
+    }
+    imports.extend(nested_enum_imports(scope.model, m, opts));
+    if opts.gson {
+        imports.push("com.google.gson.annotations.SerializedName");
+    }
+    if opts.jackson {
+        imports.push("com.fasterxml.jackson.annotation.JsonProperty");
+    }
+    if opts.jaxb {
+        imports.push(opts.annotation_namespace.xml_binding_import());
+    }
+    if opts.bean_validation && m.fields.iter().any(|f| !bean_validation(f).is_empty()) {
+        imports.push(opts.annotation_namespace.validation_import());
+    }
+    if let Some(a) = opts
+        .field_number_annotation
+        .as_deref()
+        .filter(|a| a.contains('.'))
+    {
+        imports.push(a);
+    }
+    if opts.field_accessor_map && !m.fields.is_empty() {
+        imports.extend([
+            "java.util.Collections",
+            "java.util.LinkedHashMap",
+            "java.util.Map",
+            "java.util.function.Function",
+        ]);
+    }
+    for f in &m.fields {
+        match optional_getter_type(f, scope).as_deref() {
+            Some("OptionalInt") => imports.push("java.util.OptionalInt"),
+            Some("OptionalLong") => imports.push("java.util.OptionalLong"),
+            Some("OptionalDouble") => imports.push("java.util.OptionalDouble"),
+            Some(_) => imports.push("java.util.Optional"),
+            None => {}
+        }
+    }
+    if !opts.flatten_nested {
+        for t in scope.model.nested_types(&m.name) {
+            if let TypeDecl::Message(nested) = t {
+                let nested_scope = TypeScope {
+                    message: &nested.name,
+                    ..*scope
+                };
+                collect_message_imports(&nested_scope, nested, opts, imports);
+            }
+        }
+    }
+}
+
+// The class declaration itself, with nested types rendered as members.
+fn render_message_decl(scope: &TypeScope, m: &parser::Message, opts: &JavaGenOptions) -> String {
+    let sorted;
+    let m = if opts.sort_members {
+        let mut copy = m.clone();
+        copy.fields.sort_by(|a, b| a.name.cmp(&b.name));
+        sorted = copy;
+        &sorted
+    } else {
+        m
+    };
+    let class = java_class_name(&m.name, opts);
+    let tag_annotation = opts.field_number_annotation.as_deref();
+    let is_nested = !opts.flatten_nested && m.name.contains('.');
+
     let mut s = String::new();
-    s.push_str(&render_package_line(pkg));
-    s.push_str(&format!("public class {} {{\n", m.name));
+    s.push_str(&checkstyle::doc(
+        opts,
+        "",
+        &format!("Message {{@code {}}}.", m.name),
+    ));
+    if opts.jaxb {
+        s.push_str("@XmlRootElement\n@XmlAccessorType(XmlAccessType.FIELD)\n");
+    }
+    let modifier = if is_nested { "static " } else { "" };
+    let extends = match opts.base_class_for.get(&m.name) {
+        Some(base) => format!(" extends {}", base),
+        None => String::new(),
+    };
+    s.push_str(&format!(
+        "public {}class {}{} {{\n",
+        modifier, class, extends
+    ));
+
+    // tag constants, named like protobuf-java
+    if opts.tag_constants && !m.fields.is_empty() {
+        for f in &m.fields {
+            s.push_str(&checkstyle::doc(
+                opts,
+                "    ",
+                &format!("Field number of {{@code {}}}.", f.name),
+            ));
+            s.push_str(&format!(
+                "    public static final int {}_FIELD_NUMBER = {};\n",
+                f.name.to_uppercase(),
+                f.order
+            ));
+        }
+        s.push('\n');
+    }
 
     // fields
     for f in &m.fields {
-        let jt = java_type_for(&f.ty);
-        s.push_str(&format!("    private {} {};\n", jt, f.name));
+        let jt = java_field_type(f, scope);
+        let name = scope.member(f);
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            &format!("Value of {}.", checkstyle::field_ref(f)),
+        ));
+        // JSON key: the json_name override when present, else the proto field name
+        let json_key = f.json_name.as_deref().unwrap_or(&f.name);
+        if opts.jackson {
+            s.push_str(&format!("    @JsonProperty(\"{}\")\n", json_key));
+        }
+        if opts.gson {
+            s.push_str(&format!("    @SerializedName(\"{}\")\n", json_key));
+        }
+        if opts.jaxb {
+            s.push_str(&format!("    @XmlElement(name = \"{}\")\n", f.name));
+        }
+        if let Some(a) = tag_annotation {
+            let simple = a.rsplit('.').next().unwrap_or(a);
+            s.push_str(&format!("    @{}({})\n", simple, f.order));
+        }
+        if opts.bean_validation {
+            for a in bean_validation(f) {
+                s.push_str(&format!("    {}\n", a));
+            }
+        }
+        match field_initializer(f, opts, scope) {
+            Some(init) => s.push_str(&format!("    private {} {} = {};", jt, name, init)),
+            None => s.push_str(&format!("    private {} {};", jt, name)),
+        }
+        s.push_str(&render_trailing_doc(f.trailing_doc.as_deref()));
+    }
+    let presence = presence_bits(m, opts);
+    for word in 0..presence_words(&presence) {
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            "Presence bits of the fields with explicit presence.",
+        ));
+        s.push_str(&format!("    private int {};\n", scope.presence_word(word)));
     }
     s.push('\n');
 
-    // no-arg constructor
-    s.push_str(&format!("    public {}() {{}}\n\n", m.name));
+    if opts.field_accessor_map && !m.fields.is_empty() {
+        s.push_str(&render_field_accessor_map(m, &class, opts, scope));
+    }
+
+    // constructors
+    if opts.no_arg_constructor {
+        s.push_str(&checkstyle::doc(opts, "    ", "Creates an empty instance."));
+        s.push_str(&format!("    public {}() {{}}\n\n", class));
+    }
+    if opts.all_args_constructor && !m.fields.is_empty() {
+        s.push_str(&render_all_args_constructor(
+            m, &class, opts, scope, &presence,
+        ));
+    }
 
     // getters/setters
-    for f in &m.fields {
-        let jt = java_type_for(&f.ty);
-        let cap = capitalize(&f.name);
+    for (f, bit) in m.fields.iter().zip(&presence) {
+        let jt = java_field_type(f, scope);
+        let n = scope.member(f);
+        let cap = capitalize(&n);
+        let value = checkstyle::param(opts, &jt, "value");
+        let field = checkstyle::field_ref(f);
+        let strict = opts.strict_getters && !is_repeated(f) && is_message_field(f, scope);
+        let getter_doc = match optional_getter_type(f, scope) {
+            Some(_) => "@return the value, or empty if unset",
+            None if strict => "@return the value\n@throws IllegalStateException if unset",
+            None => "@return the current value",
+        };
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            &format!("Returns {}.\n\n{}", field, getter_doc),
+        ));
+        match optional_getter_type(f, scope) {
+            Some(rt) if rt.starts_with("Optional<") => s.push_str(&format!(
+                "    public {} get{}() {{ return Optional.ofNullable(this.{}); }}\n",
+                rt, cap, n
+            )),
+            Some(rt) => s.push_str(&format!(
+                "    public {rt} get{cap}() {{ return this.{n} == null ? {rt}.empty() : {rt}.of(this.{n}); }}\n",
+                rt = rt,
+                cap = cap,
+                n = n
+            )),
+            None if strict => s.push_str(&format!(
+                "    public {jt} get{cap}() {{ if (this.{n} == null) throw new IllegalStateException(\"field {name} not set\"); return this.{n}; }}\n",
+                jt = jt,
+                cap = cap,
+                n = n,
+                name = f.name
+            )),
+            None => s.push_str(&format!(
+                "    public {} get{}() {{ return this.{}; }}\n",
+                jt, cap, n
+            )),
+        }
+        let has_doc = checkstyle::doc(
+            opts,
+            "    ",
+            &format!(
+                "Returns whether {} is set.\n\n@return {{@code true}} if set",
+                field
+            ),
+        );
+        let set_doc = checkstyle::doc(
+            opts,
+            "    ",
+            &format!("Sets {}.\n\n@param value the new value", field),
+        );
+        let clear_doc = checkstyle::doc(opts, "    ", &format!("Clears {}.", field));
+        // strict getters need a presence check even where none is tracked
+        if strict && bit.is_none() && !stored_boxed(f, scope) {
+            s.push_str(&has_doc);
+            s.push_str(&format!(
+                "    public boolean has{}() {{ return this.{} != null; }}\n",
+                cap, n
+            ));
+        }
+        let Some(bit) = bit else {
+            s.push_str(&set_doc);
+            s.push_str(&format!(
+                "    public void set{}({}) {{ this.{} = value; }}\n",
+                cap, value, n
+            ));
+            if stored_boxed(f, scope) {
+                s.push_str(&has_doc);
+                s.push_str(&format!(
+                    "    public boolean has{}() {{ return this.{} != null; }}\n",
+                    cap, n
+                ));
+                s.push_str(&clear_doc);
+                s.push_str(&format!(
+                    "    public void clear{}() {{ this.{} = null; }}\n",
+                    cap, n
+                ));
+            }
+            s.push('\n');
+            continue;
+        };
+        let (word, mask) = presence_mask(*bit);
+        let bits = scope.presence_word(word);
+        s.push_str(&set_doc);
+        s.push_str(&format!(
+            "    public void set{}({}) {{ this.{} = value; {} |= {}; }}\n",
+            cap, value, n, bits, mask
+        ));
+        s.push_str(&has_doc);
         s.push_str(&format!(
-            "    public {} get{}() {{ return this.{}; }}\n",
-            jt, cap, f.name
+            "    public boolean has{}() {{ return ({} & {}) != 0; }}\n",
+            cap, bits, mask
         ));
+        let reset = match &f.ty {
+            FieldType::Scalar(_) if stored_boxed(f, scope) => "null",
+            FieldType::Scalar(st) => scalar_default(st),
+            FieldType::Custom(_)
+            | FieldType::Message(_)
+            | FieldType::Enum(_)
+            | FieldType::Map { .. } => "null",
+        };
+        s.push_str(&clear_doc);
         s.push_str(&format!(
-            "    public void set{}({} value) {{ this.{} = value; }}\n\n",
-            cap, jt, f.name
+            "    public void clear{}() {{ this.{} = {}; {} &= ~{}; }}\n\n",
+            cap, n, reset, bits, mask
+        ));
+    }
+
+    if opts.withers {
+        s.push_str(&render_withers(m, &class, opts, scope, &presence));
+    }
+
+    if opts.builder {
+        s.push_str(&render_builder(m, opts, scope, &presence));
+    }
+
+    if let Some(bridge) = &opts.proto_bridge {
+        s.push_str(&proto_bridge::render_proto_bridge(
+            scope, m, &class, bridge, opts, &presence,
         ));
     }
 
+    if !opts.flatten_nested {
+        for (i, t) in scope.model.nested_types(&m.name).enumerate() {
+            let decl = match t {
+                TypeDecl::Message(nested) => {
+                    let nested_scope = TypeScope {
+                        message: &nested.name,
+                        ..*scope
+                    };
+                    render_message_decl(&nested_scope, nested, opts)
+                }
+                TypeDecl::Enum(e) => render_enum_decl(e, opts),
+            };
+            if i > 0 {
+                s.push('\n');
+            }
+            s.push_str(&indent(&decl));
+        }
+    }
+
     s.push_str("}\n");
     s
 }
 
-fn capitalize(s: &str) -> String {
-    let mut it = s.chars();
-    match it.next() {
-        None => String::new(),
-        Some(first) => first.to_uppercase().collect::<String>() + it.as_str(),
+fn indent(code: &str) -> String {
+    let mut s = String::with_capacity(code.len());
+    for line in code.lines() {
+        if !line.is_empty() {
+            s.push_str("    ");
+        }
+        s.push_str(line);
+        s.push('\n');
     }
+    s
 }
 
-fn java_type_for(ft: &FieldType) -> String {
-    match ft {
-        FieldType::Scalar(st) => match st {
-            ScalarType::Double => "double".into(),
-            ScalarType::Float => "float".into(),
-            ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => "int".into(),
-            ScalarType::Uint32 | ScalarType::Fixed32 => "int".into(),
-            ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => "long".into(),
-            ScalarType::Uint64 | ScalarType::Fixed64 => "long".into(),
-            ScalarType::Bool => "boolean".into(),
-            ScalarType::String => "String".into(),
-            ScalarType::Bytes => "byte[]".into(),
-        },
-        FieldType::Custom(name) => {
-            // Use simple name portion for Java type reference in same package
-            if let Some(idx) = name.rfind('.') {
-                name[idx + 1..].to_string()
-            } else {
-                name.clone()
+// JSR-380 constraints for a field. Only rules with a standard constraint
+// are mapped; others (e.g. `string.uuid`) are left to the application.
+fn bean_validation(f: &Field) -> Vec<String> {
+    let mut not_null = f.label == Some(FieldLabel::Required);
+    let (mut min_size, mut max_size) = (None, None);
+    let mut rest = Vec::new();
+    for (name, value) in &f.options {
+        let Some((kind, rule)) = name
+            .strip_prefix("(validate.rules).")
+            .and_then(|r| r.split_once('.'))
+        else {
+            continue;
+        };
+        let integral = matches!(
+            kind,
+            "int32"
+                | "int64"
+                | "uint32"
+                | "uint64"
+                | "sint32"
+                | "sint64"
+                | "fixed32"
+                | "fixed64"
+                | "sfixed32"
+                | "sfixed64"
+        );
+        match (rule, value) {
+            ("required", OptionValue::Bool(true)) if kind == "message" => not_null = true,
+            ("min_len" | "min_bytes" | "min_items", OptionValue::Int(n)) => min_size = Some(*n),
+            ("max_len" | "max_bytes" | "max_items", OptionValue::Int(n)) => max_size = Some(*n),
+            ("pattern", OptionValue::Str(p)) => {
+                rest.push(format!("@Pattern(regexp = {})", java_string_literal(p)))
             }
+            ("email", OptionValue::Bool(true)) => rest.push("@Email".to_string()),
+            // an exclusive bound past the i64 range has no inclusive equivalent
+            ("gte", OptionValue::Int(n)) if integral => {
+                rest.push(format!("@Min({})", java_long_literal(*n)))
+            }
+            ("gt", OptionValue::Int(n)) if integral => {
+                if let Some(min) = n.checked_add(1) {
+                    rest.push(format!("@Min({})", java_long_literal(min)))
+                }
+            }
+            ("lte", OptionValue::Int(n)) if integral => {
+                rest.push(format!("@Max({})", java_long_literal(*n)))
+            }
+            ("lt", OptionValue::Int(n)) if integral => {
+                if let Some(max) = n.checked_sub(1) {
+                    rest.push(format!("@Max({})", java_long_literal(max)))
+                }
+            }
+            _ => {}
         }
     }
+    let mut out = Vec::new();
+    if not_null {
+        out.push("@NotNull".to_string());
+    }
+    match (min_size, max_size) {
+        (Some(min), Some(max)) => out.push(format!("@Size(min = {}, max = {})", min, max)),
+        (Some(min), None) => out.push(format!("@Size(min = {})", min)),
+        (None, Some(max)) => out.push(format!("@Size(max = {})", max)),
+        (None, None) => {}
+    }
+    out.extend(rest);
+    out
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// `\uXXXX` would be decoded before the literal is lexed, so other control
+// characters use octal escapes.
+fn java_string_literal(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
 
-    #[test]
+// Integer literal for a `long` annotation element: beyond the int range it
+// needs the `L` suffix.
+fn java_long_literal(n: i64) -> String {
+    if i32::try_from(n).is_ok() {
+        n.to_string()
+    } else {
+        format!("{}L", n)
+    }
+}
+
+// Unmodifiable, in declaration order: field name -> getter method reference.
+fn render_field_accessor_map(
+    m: &parser::Message,
+    class: &str,
+    opts: &JavaGenOptions,
+    scope: &TypeScope,
+) -> String {
+    let map_type = format!("Map<String, Function<{}, Object>>", class);
+    let mut s = checkstyle::doc(opts, "    ", "Getter of each field, by proto field name.");
+    s.push_str(&format!(
+        "    public static final {} FIELD_ACCESSORS;\n",
+        map_type
+    ));
+    s.push_str("    static {\n");
+    s.push_str(&format!(
+        "        {} accessors = new LinkedHashMap<>();\n",
+        map_type
+    ));
+    for f in &m.fields {
+        s.push_str(&format!(
+            "        accessors.put(\"{}\", {}::get{});\n",
+            f.name,
+            class,
+            capitalize(&scope.member(f))
+        ));
+    }
+    s.push_str("        FIELD_ACCESSORS = Collections.unmodifiableMap(accessors);\n");
+    s.push_str("    }\n\n");
+    s
+}
+
+// Tracked fields passed in count as set: primitives always, references
+// unless null. `presence` may be empty when no bits are kept.
+fn render_all_args_constructor(
+    m: &parser::Message,
+    class: &str,
+    opts: &JavaGenOptions,
+    scope: &TypeScope,
+    presence: &[Option<usize>],
+) -> String {
+    let names: Vec<String> = m.fields.iter().map(|f| scope.member(f)).collect();
+    let params: Vec<String> = m
+        .fields
+        .iter()
+        .zip(&names)
+        .map(|(f, n)| checkstyle::param(opts, &java_field_type(f, scope), n))
+        .collect();
+    let tags: Vec<String> = m
+        .fields
+        .iter()
+        .zip(&names)
+        .map(|(f, n)| format!("@param {} {}", n, checkstyle::field_ref(f)))
+        .collect();
+    let mut s = checkstyle::doc(
+        opts,
+        "    ",
+        &format!(
+            "Creates an instance from every field value.\n\n{}",
+            tags.join("\n")
+        ),
+    );
+    s.push_str(&format!("    public {}({}) {{\n", class, params.join(", ")));
+    for n in &names {
+        s.push_str(&format!("        this.{} = {};\n", n, n));
+    }
+    for (i, f) in m.fields.iter().enumerate() {
+        let Some(&Some(bit)) = presence.get(i) else {
+            continue;
+        };
+        let (word, mask) = presence_mask(bit);
+        let bits = scope.presence_word(word);
+        if is_primitive(&java_field_type(f, scope)) {
+            s.push_str(&format!("        {} |= {};\n", bits, mask));
+        } else {
+            s.push_str(&format!(
+                "        if ({} != null) {} |= {};\n",
+                names[i], bits, mask
+            ));
+        }
+    }
+    s.push_str("    }\n\n");
+    s
+}
+
+// `withX(value)`: a shallow copy with one field replaced. The all-args
+// constructor is used when there is no no-arg one.
+fn render_withers(
+    m: &parser::Message,
+    class: &str,
+    opts: &JavaGenOptions,
+    scope: &TypeScope,
+    presence: &[Option<usize>],
+) -> String {
+    let names: Vec<String> = m.fields.iter().map(|f| scope.member(f)).collect();
+    let mut s = String::new();
+    for ((f, n), bit) in m.fields.iter().zip(&names).zip(presence) {
+        let value = |g: &String| {
+            if g == n {
+                g.clone()
+            } else {
+                format!("this.{}", g)
+            }
+        };
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            &format!(
+                "Returns a copy with {} replaced.\n\n@param {} the new value\n@return the copy",
+                checkstyle::field_ref(f),
+                n
+            ),
+        ));
+        s.push_str(&format!(
+            "    public {} with{}({}) {{\n",
+            class,
+            capitalize(n),
+            checkstyle::param(opts, &java_field_type(f, scope), n)
+        ));
+        let all_args = !opts.no_arg_constructor && opts.all_args_constructor;
+        let args: Vec<String> = names.iter().map(value).collect();
+        if all_args && presence_words(presence) == 0 {
+            s.push_str(&format!(
+                "        return new {}({});\n",
+                class,
+                args.join(", ")
+            ));
+        } else {
+            if all_args {
+                s.push_str(&format!(
+                    "        {} copy = new {}({});\n",
+                    class,
+                    class,
+                    args.join(", ")
+                ));
+            } else {
+                s.push_str(&format!("        {} copy = new {}();\n", class, class));
+                for (g, v) in names.iter().zip(&args) {
+                    s.push_str(&format!("        copy.{} = {};\n", g, v));
+                }
+            }
+            // the source's bits, plus the replaced field's
+            for word in 0..presence_words(presence) {
+                s.push_str(&format!(
+                    "        copy.{0} = this.{0};\n",
+                    scope.presence_word(word)
+                ));
+            }
+            if let Some(bit) = bit {
+                let (word, mask) = presence_mask(*bit);
+                s.push_str(&format!(
+                    "        copy.{} |= {};\n",
+                    scope.presence_word(word),
+                    mask
+                ));
+            }
+            s.push_str("        return copy;\n");
+        }
+        s.push_str("    }\n\n");
+    }
+    s
+}
+
+// The builder keeps its own presence bits (same layout as the message's),
+// carried over by toBuilder() and copied onto the built message.
+fn render_builder(
+    m: &parser::Message,
+    opts: &JavaGenOptions,
+    scope: &TypeScope,
+    presence: &[Option<usize>],
+) -> String {
+    let class = java_class_name(&m.name, opts);
+    let names: Vec<String> = m.fields.iter().map(|f| scope.member(f)).collect();
+    let mut s = String::new();
+    s.push_str(&checkstyle::doc(
+        opts,
+        "    ",
+        "Returns an empty builder.\n\n@return a new builder",
+    ));
+    s.push_str("    public static Builder newBuilder() { return new Builder(); }\n\n");
+
+    // copy-and-modify: a builder pre-populated from this instance
+    s.push_str(&checkstyle::doc(
+        opts,
+        "    ",
+        "Returns a builder holding this message's values.\n\n@return a new builder",
+    ));
+    s.push_str("    public Builder toBuilder() {\n");
+    s.push_str("        Builder builder = new Builder();\n");
+    for (f, n) in m.fields.iter().zip(&names) {
+        if is_repeated(f) {
+            s.push_str(&format!(
+                "        builder.{0} = this.{0} == null ? new ArrayList<>() : new ArrayList<>(this.{0});\n",
+                n
+            ));
+        } else {
+            s.push_str(&format!("        builder.{0} = this.{0};\n", n));
+        }
+    }
+    // presence, including that of required fields, carries over as is
+    for word in 0..presence_words(presence) {
+        s.push_str(&format!(
+            "        builder.{0} = this.{0};\n",
+            scope.presence_word(word)
+        ));
+    }
+    s.push_str("        return builder;\n");
+    s.push_str("    }\n\n");
+    s.push_str(&checkstyle::doc(
+        opts,
+        "    ",
+        &format!("Builder of {{@code {}}}.", class),
+    ));
+    s.push_str("    public static class Builder {\n");
+    for (f, n) in m.fields.iter().zip(&names) {
+        let jt = java_field_type(f, scope);
+        s.push_str(&checkstyle::doc(
+            opts,
+            "        ",
+            &format!("Value of {}.", checkstyle::field_ref(f)),
+        ));
+        if is_repeated(f) {
+            s.push_str(&format!(
+                "        private {} {} = new ArrayList<>();\n",
+                jt, n
+            ));
+        } else {
+            s.push_str(&format!("        private {} {};\n", jt, n));
+        }
+    }
+    for word in 0..presence_words(presence) {
+        s.push_str(&checkstyle::doc(
+            opts,
+            "        ",
+            "Presence bits of the fields with explicit presence.",
+        ));
+        s.push_str(&format!(
+            "        private int {};\n",
+            scope.presence_word(word)
+        ));
+    }
+    s.push('\n');
+
+    for ((f, n), bit) in m.fields.iter().zip(&names).zip(presence) {
+        let jt = java_field_type(f, scope);
+        let cap = capitalize(n);
+        let field = checkstyle::field_ref(f);
+        let set_doc = checkstyle::doc(
+            opts,
+            "        ",
+            &format!(
+                "Sets {}.\n\n@param value the new value\n@return this builder",
+                field
+            ),
+        );
+        if let Some(bit) = bit {
+            let (word, mask) = presence_mask(*bit);
+            s.push_str(&set_doc);
+            s.push_str(&format!(
+                "        public Builder set{}({}) {{ this.{} = value; {} |= {}; return this; }}\n",
+                cap,
+                checkstyle::param(opts, &jt, "value"),
+                n,
+                scope.presence_word(word),
+                mask
+            ));
+        } else if is_repeated(f) {
+            let et = boxed(&java_type_for(&f.ty, scope));
+            s.push_str(&checkstyle::doc(
+                opts,
+                "        ",
+                &format!(
+                    "Replaces the elements of {}.\n\n@param values the new elements\n@return this builder",
+                    field
+                ),
+            ));
+            s.push_str(&format!(
+                "        public Builder set{}({}) {{ this.{} = new ArrayList<>(values); return this; }}\n",
+                cap,
+                checkstyle::param(opts, &jt, "values"),
+                n
+            ));
+            s.push_str(&checkstyle::doc(
+                opts,
+                "        ",
+                &format!(
+                    "Appends an element to {}.\n\n@param value the element to add\n@return this builder",
+                    field
+                ),
+            ));
+            s.push_str(&format!(
+                "        public Builder add{}({}) {{ this.{}.add(value); return this; }}\n",
+                cap,
+                checkstyle::param(opts, &et, "value"),
+                n
+            ));
+            s.push_str(&checkstyle::doc(
+                opts,
+                "        ",
+                &format!(
+                    "Appends elements to {}.\n\n@param values the elements to add\n@return this builder",
+                    field
+                ),
+            ));
+            s.push_str(&format!(
+                "        public Builder addAll{}({}) {{ this.{}.addAll(values); return this; }}\n",
+                cap,
+                checkstyle::param(opts, &jt, "values"),
+                n
+            ));
+        } else {
+            s.push_str(&set_doc);
+            s.push_str(&format!(
+                "        public Builder set{}({}) {{ this.{} = value; return this; }}\n",
+                cap,
+                checkstyle::param(opts, &jt, "value"),
+                n
+            ));
+        }
+    }
+    s.push('\n');
+
+    let mut checks = String::new();
+    // primitives by presence bit (required fields always have one), the
+    // rest against null
+    for ((f, n), bit) in m.fields.iter().zip(&names).zip(presence) {
+        if !opts.validate_on_build || f.label != Some(FieldLabel::Required) {
+            continue;
+        }
+        let unset = match bit {
+            Some(bit) if is_primitive(&java_field_type(f, scope)) => {
+                let (word, mask) = presence_mask(*bit);
+                format!("({} & {}) == 0", scope.presence_word(word), mask)
+            }
+            _ => format!("this.{} == null", n),
+        };
+        checks.push_str(&format!(
+            "            if ({}) throw new IllegalStateException(\"required field `{}` is not set\");\n",
+            unset, f.name
+        ));
+    }
+    let throws = if checks.is_empty() {
+        ""
+    } else {
+        "\n@throws IllegalStateException if a required field is not set"
+    };
+    s.push_str(&checkstyle::doc(
+        opts,
+        "        ",
+        &format!(
+            "Builds the message.\n\n@return a new {{@code {}}}{}",
+            class, throws
+        ),
+    ));
+    s.push_str(&format!("        public {} build() {{\n", class));
+    s.push_str(&checks);
+    let all_args = !opts.no_arg_constructor && opts.all_args_constructor && !m.fields.is_empty();
+    // lists are copied, so adding to the builder later leaves the message alone
+    let args: Vec<String> = m
+        .fields
+        .iter()
+        .zip(&names)
+        .map(|(f, n)| {
+            if is_repeated(f) {
+                format!("new ArrayList<>(this.{})", n)
+            } else {
+                format!("this.{}", n)
+            }
+        })
+        .collect();
+    if all_args && presence_words(presence) == 0 {
+        s.push_str(&format!(
+            "            return new {}({});\n",
+            class,
+            args.join(", ")
+        ));
+    } else {
+        if all_args {
+            s.push_str(&format!(
+                "            {} result = new {}({});\n",
+                class,
+                class,
+                args.join(", ")
+            ));
+        } else {
+            s.push_str(&format!(
+                "            {} result = new {}();\n",
+                class, class
+            ));
+            for (n, arg) in names.iter().zip(&args) {
+                s.push_str(&format!("            result.{} = {};\n", n, arg));
+            }
+        }
+        // the builder's bits win over the constructor's guesses
+        for word in 0..presence_words(presence) {
+            s.push_str(&format!(
+                "            result.{0} = this.{0};\n",
+                scope.presence_word(word)
+            ));
+        }
+        s.push_str("            return result;\n");
+    }
+    s.push_str("        }\n");
+    s.push_str("    }\n");
+    s
+}
+
+// Bit index per field (parallel to m.fields) for fields with explicit
+// presence, and for required ones when build() checks them.
+fn presence_bits(m: &parser::Message, opts: &JavaGenOptions) -> Vec<Option<usize>> {
+    let checks_required = opts.builder && opts.validate_on_build;
+    let mut next = 0;
+    m.fields
+        .iter()
+        .map(|f| {
+            let tracked = match f.label {
+                Some(FieldLabel::Optional) => opts.presence_bitset,
+                Some(FieldLabel::Required) => opts.presence_bitset || checks_required,
+                _ => false,
+            };
+            tracked.then(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
+}
+
+// Number of `bitFieldN_` words needed for `presence_bits`.
+fn presence_words(presence: &[Option<usize>]) -> usize {
+    presence.iter().flatten().max().map_or(0, |b| b / 32 + 1)
+}
+
+// (word index, hex mask) of a presence bit
+fn presence_mask(bit: usize) -> (usize, String) {
+    (bit / 32, format!("0x{:08x}", 1u32 << (bit % 32)))
+}
+
+fn is_primitive(java_type: &str) -> bool {
+    java_type.starts_with(|c: char| c.is_ascii_lowercase()) && !java_type.ends_with("[]")
+}
+
+fn is_repeated(f: &Field) -> bool {
+    f.label == Some(FieldLabel::Repeated)
+}
+
+fn is_map(f: &Field) -> bool {
+    matches!(f.ty, FieldType::Map { .. })
+}
+
+// Java type of the field declaration: repeated fields become List<Boxed>.
+fn java_field_type(f: &Field, scope: &TypeScope) -> String {
+    let jt = java_type_for(&f.ty, scope);
+    if is_repeated(f) || stored_boxed(f, scope) {
+        let b = boxed(&jt);
+        if is_repeated(f) {
+            format!("List<{}>", b)
+        } else {
+            b
+        }
+    } else {
+        jt
+    }
+}
+
+// proto3 `optional` fields (and any `optional` field under optional_getters)
+// are stored boxed so that null means unset.
+fn stored_boxed(f: &Field, scope: &TypeScope) -> bool {
+    f.label == Some(FieldLabel::Optional)
+        && (scope.optional_getters || scope.model.syntax == ProtoSyntax::Proto3)
+}
+
+// Getter return type under optional_getters, None for a plain getter.
+fn optional_getter_type(f: &Field, scope: &TypeScope) -> Option<String> {
+    if !scope.optional_getters || is_repeated(f) {
+        return None;
+    }
+    let jt = java_type_for(&f.ty, scope);
+    if f.label != Some(FieldLabel::Optional) && !is_message_field(f, scope) {
+        return None;
+    }
+    Some(match jt.as_str() {
+        "int" => "OptionalInt".to_string(),
+        "long" => "OptionalLong".to_string(),
+        "double" => "OptionalDouble".to_string(),
+        _ => format!("Optional<{}>", boxed(&jt)),
+    })
+}
+
+// Field (singular or repeated) of a message type. Types not declared in this
+// file are assumed to be messages.
+fn is_message_field(f: &Field, scope: &TypeScope) -> bool {
+    match &f.ty {
+        FieldType::Scalar(_) | FieldType::Enum(_) | FieldType::Map { .. } => false,
+        FieldType::Message(_) => true,
+        FieldType::Custom(name) => !scope
+            .model
+            .resolve_type_name(scope.message, name)
+            .is_some_and(|n| {
+                scope
+                    .model
+                    .types
+                    .iter()
+                    .any(|t| matches!(t, TypeDecl::Enum(e) if e.name == n))
+            }),
+    }
+}
+
+fn boxed(jt: &str) -> String {
+    match jt {
+        "int" => "Integer".into(),
+        "long" => "Long".into(),
+        "double" => "Double".into(),
+        "float" => "Float".into(),
+        "boolean" => "Boolean".into(),
+        other => other.to_string(),
+    }
+}
+
+// Proto3 implicit presence: unlabeled scalar fields outside a oneof start at
+// their zero value. explicit_defaults extends that to every field not tracked
+// through null.
+fn field_initializer(f: &Field, opts: &JavaGenOptions, scope: &TypeScope) -> Option<&'static str> {
+    if opts.explicit_defaults && is_repeated(f) {
+        return Some("new ArrayList<>()");
+    }
+    if opts.explicit_defaults && is_map(f) {
+        return Some("new HashMap<>()");
+    }
+    let initialized = if opts.explicit_defaults {
+        !stored_boxed(f, scope)
+    } else {
+        opts.proto3_implicit_defaults
+            && scope.model.syntax == ProtoSyntax::Proto3
+            && f.label.is_none()
+            && f.oneof.is_none()
+    };
+    if !initialized {
+        return None;
+    }
+    match &f.ty {
+        FieldType::Scalar(st) => Some(scalar_default(st)),
+        FieldType::Custom(_)
+        | FieldType::Message(_)
+        | FieldType::Enum(_)
+        | FieldType::Map { .. } => None,
+    }
+}
+
+fn scalar_default(st: &ScalarType) -> &'static str {
+    match st {
+        ScalarType::Double => "0.0",
+        ScalarType::Float => "0.0f",
+        ScalarType::Int32
+        | ScalarType::Sint32
+        | ScalarType::Sfixed32
+        | ScalarType::Uint32
+        | ScalarType::Fixed32 => "0",
+        ScalarType::Int64
+        | ScalarType::Sint64
+        | ScalarType::Sfixed64
+        | ScalarType::Uint64
+        | ScalarType::Fixed64 => "0L",
+        ScalarType::Bool => "false",
+        ScalarType::String => "\"\"",
+        ScalarType::Bytes => "new byte[0]",
+    }
+}
+
+// `PAID_IN_FULL` -> `PaidInFull`
+fn upper_camel(s: &str) -> String {
+    s.split('_')
+        .filter(|p| !p.is_empty())
+        .map(|p| capitalize(&p.to_lowercase()))
+        .collect()
+}
+
+fn capitalize(s: &str) -> String {
+    let mut it = s.chars();
+    match it.next() {
+        None => String::new(),
+        Some(first) => first.to_uppercase().collect::<String>() + it.as_str(),
+    }
+}
+
+// Where custom type references are resolved from: the file's package, the
+// enclosing message, and (for multi-file generation) the type registry.
+#[derive(Clone, Copy)]
+struct TypeScope<'a> {
+    package: Option<&'a str>,
+    message: &'a str,
+    model: &'a ProtoModel,
+    registry: Option<&'a TypeRegistry>,
+    flatten_nested: bool,
+    optional_getters: bool,
+    class_prefix: &'a str,
+    class_suffix: &'a str,
+    reference_style: ReferenceStyle,
+    package_mapping: &'a [(String, String)],
+    java_time: bool,
+    camel_case_members: bool,
+}
+
+impl TypeScope<'_> {
+    // Java package of the file being generated
+    fn java_package(&self) -> Option<String> {
+        map_package(self.package_mapping, self.package)
+    }
+
+    // Apply the class prefix/suffix to each segment of a dotted class path
+    fn affixed(&self, path: &str) -> String {
+        path.split('.')
+            .map(|seg| format!("{}{}{}", self.class_prefix, seg, self.class_suffix))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    // Java name of a field and its accessors' stem, within the message being
+    // rendered: the proto name, or its camelCase form when that is neither a
+    // keyword nor the name of another field of the message.
+    fn member(&self, f: &Field) -> String {
+        if !self.camel_case_members || !f.name.contains('_') {
+            return f.name.clone();
+        }
+        let camel = lower_camel(&f.name);
+        let taken = |name: &str| {
+            self.model.types.iter().any(|t| match t {
+                TypeDecl::Message(m) if m.name == self.message => m
+                    .fields
+                    .iter()
+                    .any(|g| g.name != f.name && (g.name == name || lower_camel(&g.name) == name)),
+                _ => false,
+            })
+        };
+        if camel.is_empty() || JAVA_KEYWORDS.contains(&camel.as_str()) || taken(&camel) {
+            f.name.clone()
+        } else {
+            camel
+        }
+    }
+
+    // Name of the n-th presence word; Checkstyle rejects the `_` suffix.
+    fn presence_word(&self, word: usize) -> String {
+        if self.camel_case_members {
+            format!("bitField{}", word)
+        } else {
+            format!("bitField{}_", word)
+        }
+    }
+}
+
+const JAVA_KEYWORDS: [&str; 53] = [
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "true",
+    "false",
+    "null",
+];
+
+// `created_at` -> `createdAt`; leading and repeated underscores are dropped.
+fn lower_camel(name: &str) -> String {
+    let mut s = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = !s.is_empty();
+        } else if upper {
+            s.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
+// Java package for a proto package under the longest matching prefix rule.
+fn map_package(mapping: &[(String, String)], package: Option<&str>) -> Option<String> {
+    let package = package?;
+    let rule = mapping
+        .iter()
+        .filter(|(from, _)| {
+            package
+                .strip_prefix(from.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+        .max_by_key(|(from, _)| from.len());
+    Some(match rule {
+        Some((from, to)) => format!("{}{}", to, &package[from.len()..]),
+        None => package.to_string(),
+    })
+}
+
+// Java type and import for a well-known type under `java_time`.
+fn well_known_type(name: &str, scope: &TypeScope) -> Option<(&'static str, Option<&'static str>)> {
+    if !scope.java_time {
+        return None;
+    }
+    Some(match name.trim_start_matches('.') {
+        "google.protobuf.Timestamp" => ("Instant", Some("java.time.Instant")),
+        "google.protobuf.Duration" => ("Duration", Some("java.time.Duration")),
+        "google.protobuf.DoubleValue" => ("Double", None),
+        "google.protobuf.FloatValue" => ("Float", None),
+        "google.protobuf.Int64Value" | "google.protobuf.UInt64Value" => ("Long", None),
+        "google.protobuf.Int32Value" | "google.protobuf.UInt32Value" => ("Integer", None),
+        "google.protobuf.BoolValue" => ("Boolean", None),
+        "google.protobuf.StringValue" => ("String", None),
+        "google.protobuf.BytesValue" => ("byte[]", None),
+        _ => return None,
+    })
+}
+
+// Registry entry of `name` when it is declared in another package.
+fn foreign_type<'a>(name: &str, scope: &TypeScope<'a>) -> Option<&'a JavaTypeRef> {
+    scope
+        .registry
+        .and_then(|reg| reg.resolve(scope.package, scope.message, name))
+        .filter(|r| r.package.as_deref() != scope.package)
+}
+
+// With ReferenceStyle::Import, the import bringing a cross-package type's
+// top-level class into scope, unless its name clashes with a local type.
+// `java.time` classes for well-known types are imported regardless.
+fn type_import(name: &str, scope: &TypeScope) -> Option<String> {
+    if let Some((_, import)) = well_known_type(name, scope) {
+        return import.map(str::to_string);
+    }
+    if scope.reference_style != ReferenceStyle::Import {
+        return None;
+    }
+    let r = foreign_type(name, scope)?;
+    let package = map_package(scope.package_mapping, r.package.as_deref())?;
+    let top = scope.affixed(r.class_name.split('.').next().unwrap_or(&r.class_name));
+    let clashes = scope
+        .model
+        .types
+        .iter()
+        .any(|t| !t.name().contains('.') && scope.affixed(t.name()) == top);
+    (!clashes).then(|| format!("{}.{}", package, top))
+}
+
+// Java class name for a (possibly nested) proto type name.
+fn java_class_name(proto_name: &str, opts: &JavaGenOptions) -> String {
+    let name = if opts.flatten_nested {
+        proto_name.replace('.', "")
+    } else {
+        // nested types are member classes named by their last segment
+        proto_name
+            .rsplit('.')
+            .next()
+            .unwrap_or(proto_name)
+            .to_string()
+    };
+    format!("{}{}{}", opts.class_prefix, name, opts.class_suffix)
+}
+
+fn java_type_for(ft: &FieldType, scope: &TypeScope) -> String {
+    match ft {
+        FieldType::Scalar(st) => match st {
+            ScalarType::Double => "double".into(),
+            ScalarType::Float => "float".into(),
+            ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => "int".into(),
+            ScalarType::Uint32 | ScalarType::Fixed32 => "int".into(),
+            ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => "long".into(),
+            ScalarType::Uint64 | ScalarType::Fixed64 => "long".into(),
+            ScalarType::Bool => "boolean".into(),
+            ScalarType::String => "String".into(),
+            ScalarType::Bytes => "byte[]".into(),
+        },
+        FieldType::Map { key, value } => format!(
+            "Map<{}, {}>",
+            boxed(&java_type_for(&FieldType::Scalar(key.clone()), scope)),
+            boxed(&java_type_for(value, scope))
+        ),
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => {
+            if let Some((java, _)) = well_known_type(name, scope) {
+                return java.into();
+            }
+            // Types from another package are imported or referenced fully qualified
+            if let Some(r) = foreign_type(name, scope) {
+                let class = scope.affixed(&r.class_name);
+                if type_import(name, scope).is_some() {
+                    return class;
+                }
+                return match map_package(scope.package_mapping, r.package.as_deref()) {
+                    Some(p) => format!("{}.{}", p, class),
+                    None => class,
+                };
+            }
+            let Some(local) = scope.model.resolve_type_name(scope.message, name) else {
+                // Unknown here: use simple name portion, assuming the same package
+                return scope.affixed(name.rsplit('.').next().unwrap_or(name));
+            };
+            if scope.flatten_nested {
+                return scope.affixed(&local.replace('.', ""));
+            }
+            // A member class of the current or an enclosing class is in scope
+            // by its simple name; anything else needs its outer-class path.
+            match local.rsplit_once('.') {
+                Some((outer, simple))
+                    if scope.message == outer
+                        || scope.message.starts_with(&format!("{}.", outer)) =>
+                {
+                    scope.affixed(simple)
+                }
+                _ => scope.affixed(&local),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const ORDER_PROTO: &str = "../parser/tests/resources/order.proto";
+    const COMPLEX_PROTO: &str = "tests/resources/complex.proto";
+
+    // Source of the file ending in `name` generated from `proto`, a `.proto`
+    // path or inline source.
+    pub(crate) fn gen_file(proto: &str, opts: &JavaGenOptions, name: &str) -> String {
+        let model = if proto.ends_with(".proto") {
+            parse_proto_file(proto).unwrap()
+        } else {
+            parser::parse_proto_str(proto).unwrap()
+        };
+        let suffix = format!("/{}", name);
+        generate_java_with_options(&model, opts)
+            .into_iter()
+            .find(|(p, _)| p == name || p.ends_with(&suffix))
+            .unwrap_or_else(|| panic!("{} not generated", name))
+            .1
+    }
+
+    #[test]
     fn generate_from_order_proto_smoke() {
         // Use the shared fixture from the parser crate
-        let path = Path::new("../parser/tests/resources/order.proto");
+        let path = Path::new(ORDER_PROTO);
         let files = generate_java_from_proto(path).expect("generation should succeed");
         assert!(!files.is_empty());
         // Expect at least one class or enum generated and containing package line
         let any_has_package = files.iter().any(|(_, src)| src.contains("package "));
         assert!(any_has_package);
     }
+
+    #[test]
+    fn proto3_implicit_defaults_initialize_scalars() {
+        let opts = JavaGenOptions {
+            proto3_implicit_defaults: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("private int id = 0;"));
+        assert!(order.contains("private String name = \"\";"));
+        // Message-typed fields keep Java's null default
+        assert!(order.contains("private Address shipping_address;"));
+
+        // `optional` opts into explicit presence, so no implicit default applies
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("private Long count;"));
+        assert!(item.contains("    public boolean hasCount() { return this.count != null; }\n"));
+        // implicit-presence scalars get a bare accessor pair, no has/clear
+        assert!(order.contains(
+            "    public int getId() { return this.id; }\n    \
+             public void setId(int value) { this.id = value; }\n\n"
+        ));
+        assert!(order.contains("        private String street = \"\";\n"));
+
+        // oneof members have explicit presence; proto2 has no implicit presence
+        let proto = r#"
+            syntax = "proto3";
+            message Payment { oneof method { string card = 1; int32 voucher = 2; } int32 amount = 3; }
+        "#;
+        let payment = gen_file(proto, &opts, "Payment.java");
+        assert!(payment.contains("    private String card;\n    private int voucher;\n"));
+        assert!(payment.contains("    private int amount = 0;\n"));
+        let legacy = gen_file(
+            "syntax = \"proto2\"; message Legacy { optional int32 a = 1; int32 b = 2; }",
+            &opts,
+            "Legacy.java",
+        );
+        assert!(legacy.contains("    private int b;\n"));
+        assert!(!legacy.contains(" = 0;"));
+    }
+
+    #[test]
+    fn explicit_defaults_initialize_fields_without_null_presence() {
+        let opts = JavaGenOptions {
+            explicit_defaults: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("private int id = 0;"));
+        assert!(order.contains("private String name = \"\";"));
+        assert!(order.contains("private List<OrderItem> items = new ArrayList<>();"));
+        assert!(order.contains("import java.util.ArrayList;\n"));
+        assert!(order.contains("private Address shipping_address;"));
+        assert!(order.contains("private Status status;"));
+
+        // null still means unset for proto3 `optional`
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("private Long count;"));
+    }
+
+    #[test]
+    fn builder_has_collection_add_methods() {
+        let opts = JavaGenOptions {
+            builder: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("import java.util.List;"));
+        assert!(order.contains("private List<OrderItem> items;"));
+        assert!(order.contains("public static class Builder {"));
+        assert!(order.contains("public Builder setItems(List<OrderItem> values)"));
+        assert!(order.contains("public Builder addItems(OrderItem value)"));
+        assert!(order.contains("public Builder addAllItems(List<OrderItem> values)"));
+        assert!(order.contains("public Builder setId(int value)"));
+        assert!(!order.contains("addId"));
+        // the built message does not share the builder's list
+        assert!(order.contains("            result.items = new ArrayList<>(this.items);\n"));
+        assert!(order.contains("            result.id = this.id;\n"));
+
+        let opts = JavaGenOptions {
+            builder: true,
+            no_arg_constructor: false,
+            all_args_constructor: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains(
+            "            return new Order(this.id, this.name, new ArrayList<>(this.items), this.shipping_address, this.status);\n"
+        ));
+    }
+
+    #[test]
+    fn no_arg_constructor_can_be_suppressed() {
+        let opts = JavaGenOptions {
+            no_arg_constructor: false,
+            all_args_constructor: true,
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(!item.contains("public OrderItem() {}"));
+        assert!(item.contains(
+            "public OrderItem(String name, Long count, OrderItemType type, double price_decimal, long price_cents) {"
+        ));
+        assert_eq!(item.matches("public OrderItem(").count(), 1);
+    }
+
+    #[test]
+    fn gson_mode_annotates_fields_with_proto_names() {
+        let opts = JavaGenOptions {
+            gson: true,
+            ..Default::default()
+        };
+        let order = gen_file(COMPLEX_PROTO, &opts, "Order.java");
+        assert!(order.contains("import com.google.gson.annotations.SerializedName;"));
+        assert!(
+            order.contains("    @SerializedName(\"created_at\")\n    private long created_at;")
+        );
+        assert!(order.contains("    @SerializedName(\"id\")\n    private String id;"));
+    }
+
+    #[test]
+    fn tag_constants_use_field_numbers() {
+        let model = parse_proto_file(COMPLEX_PROTO).unwrap();
+        let opts = JavaGenOptions {
+            tag_constants: true,
+            ..Default::default()
+        };
+        let order = gen_file(COMPLEX_PROTO, &opts, "Order.java");
+        assert!(order.contains("public static final int ID_FIELD_NUMBER = 1;"));
+        assert!(order.contains("public static final int CUSTOMER_FIELD_NUMBER = 2;"));
+        assert!(order.contains("public static final int CREATED_AT_FIELD_NUMBER = 5;"));
+
+        let plain = generate_java_from_model(&model);
+        assert!(!plain.iter().any(|(_, src)| src.contains("_FIELD_NUMBER")));
+    }
+
+    #[test]
+    fn builder_to_builder_copies_fields() {
+        let model = parse_proto_file(ORDER_PROTO).unwrap();
+        let opts = JavaGenOptions {
+            builder: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("    public Builder toBuilder() {"));
+        assert!(order.contains("        builder.id = this.id;"));
+        assert!(order.contains("        builder.status = this.status;"));
+        assert!(order.contains(
+            "        builder.items = this.items == null ? new ArrayList<>() : new ArrayList<>(this.items);"
+        ));
+        assert!(
+            !generate_java_from_model(&model)
+                .iter()
+                .any(|(_, src)| src.contains("toBuilder"))
+        );
+    }
+
+    #[test]
+    fn json_annotations_prefer_json_name_override() {
+        let proto = r#"
+            syntax = "proto3";
+            message Account {
+                string id = 1;
+                string display_name = 2 [json_name = "customKey"];
+            }
+        "#;
+        let model = parser::parse_proto_str(proto).unwrap();
+        assert_eq!(
+            model.types.iter().find_map(|t| match t {
+                TypeDecl::Message(m) => m.field_by_name("display_name")?.json_name.clone(),
+                _ => None,
+            }),
+            Some("customKey".to_string())
+        );
+
+        let opts = JavaGenOptions {
+            jackson: true,
+            gson: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Account.java");
+        assert!(src.contains("import com.fasterxml.jackson.annotation.JsonProperty;"));
+        assert!(src.contains("    @JsonProperty(\"id\")\n"));
+        assert!(src.contains(
+            "    @JsonProperty(\"customKey\")\n    @SerializedName(\"customKey\")\n    private String display_name;"
+        ));
+    }
+
+    #[test]
+    fn checked_generation_surfaces_validation_errors() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Broken {
+                int32 a = 1;
+                Unknown b = 1;
+            }
+        "#,
+        )
+        .unwrap();
+        let errors = generate_java_checked(&model, &JavaGenOptions::default()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.contains(&ValidationError::DuplicateTag {
+            message: "Broken".into(),
+            tag: 1
+        }));
+
+        let valid = parse_proto_file(COMPLEX_PROTO).unwrap();
+        let files = generate_java_checked(&valid, &JavaGenOptions::default()).unwrap();
+        assert_eq!(files, generate_java_from_model(&valid));
+    }
+
+    #[test]
+    fn file_header_is_emitted_before_package() {
+        let model = parse_proto_file(COMPLEX_PROTO).unwrap();
+        let opts = JavaGenOptions {
+            file_header: Some("Copyright 2025 Example Corp.\n\nLicensed under MIT.".into()),
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        let expected = "/*\n * Copyright 2025 Example Corp.\n *\n * Licensed under MIT.\n */\n// Code generated by rs-proto-to-pojo; DO NOT EDIT.\npackage com.example.shop;";
+        for (path, src) in &files {
+            assert!(src.starts_with(expected), "{} missing header", path);
+        }
+        // both classes and enums get it
+        assert!(files.iter().any(|(p, _)| p.ends_with("/OrderStatus.java")));
+    }
+
+    #[test]
+    fn generated_marker_and_annotation() {
+        let model = parse_proto_file(COMPLEX_PROTO).unwrap();
+        for (_, src) in generate_java_from_model(&model) {
+            assert!(src.starts_with("// Code generated by rs-proto-to-pojo; DO NOT EDIT.\n"));
+            assert!(!src.contains("@javax.annotation.processing.Generated"));
+        }
+
+        let opts = JavaGenOptions {
+            generated_annotation: true,
+            ..Default::default()
+        };
+        let order = gen_file(COMPLEX_PROTO, &opts, "Order.java");
+        assert!(order.contains(
+            "@javax.annotation.processing.Generated(\"rs-proto-to-pojo\")\npublic class Order {"
+        ));
+        let status = gen_file(COMPLEX_PROTO, &opts, "OrderStatus.java");
+        assert!(status.contains(
+            "@javax.annotation.processing.Generated(\"rs-proto-to-pojo\")\npublic enum OrderStatus {"
+        ));
+    }
+
+    #[test]
+    fn flatten_nested_emits_top_level_types() {
+        let model = parse_proto_file(ORDER_PROTO).unwrap();
+        let opts = JavaGenOptions {
+            flatten_nested: true,
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        assert!(paths.contains(&"me/alekseinovikov/proto/OrderStatus.java"));
+        assert!(paths.contains(&"me/alekseinovikov/proto/OrderAddress.java"));
+        assert!(!paths.contains(&"me/alekseinovikov/proto/Order.Status.java"));
+
+        let status = gen_file(ORDER_PROTO, &opts, "OrderStatus.java");
+        assert!(status.contains("public enum OrderStatus {"));
+        assert!(status.contains("    OrderStatus(int number)"));
+
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("private OrderStatus status;"));
+        assert!(order.contains("private OrderAddress shipping_address;"));
+        assert!(order.contains("public OrderStatus getStatus()"));
+    }
+
+    #[test]
+    fn presence_bitset_tracks_optional_fields() {
+        let opts = JavaGenOptions {
+            presence_bitset: true,
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("    private int bitField0_;\n"));
+        // setCount flips the bit hasCount() reads; clearCount() resets both
+        assert!(item.contains(
+            "public void setCount(Long value) { this.count = value; bitField0_ |= 0x00000001; }"
+        ));
+        assert!(
+            item.contains("public boolean hasCount() { return (bitField0_ & 0x00000001) != 0; }")
+        );
+        assert!(item.contains(
+            "public void clearCount() { this.count = null; bitField0_ &= ~0x00000001; }"
+        ));
+        // implicit-presence fields are untouched
+        assert!(item.contains("public void setName(String value) { this.name = value; }"));
+        assert!(!item.contains("hasName"));
+
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(!order.contains("bitField0_"));
+    }
+
+    #[test]
+    fn presence_bitset_survives_builder_and_withers() {
+        let proto = r#"
+            syntax = "proto2";
+            package shop;
+            message Cart {
+                optional int32 count = 1;
+                optional string note = 2;
+                repeated string tags = 3;
+            }
+        "#;
+        let opts = JavaGenOptions {
+            presence_bitset: true,
+            builder: true,
+            withers: true,
+            all_args_constructor: true,
+            ..Default::default()
+        };
+        let cart = gen_file(proto, &opts, "shop/Cart.java");
+        // the builder tracks bits itself and hands them to the result
+        assert!(cart.contains("        private int bitField0_;\n"));
+        assert!(cart.contains(
+            "public Builder setCount(int value) { this.count = value; bitField0_ |= 0x00000001; return this; }"
+        ));
+        assert!(cart.contains(
+            "            result.bitField0_ = this.bitField0_;\n            return result;\n"
+        ));
+        assert!(
+            cart.contains(
+                "        builder.bitField0_ = this.bitField0_;\n        return builder;\n"
+            )
+        );
+        // constructor arguments count as set
+        assert!(cart.contains(
+            "        this.tags = tags;\n        bitField0_ |= 0x00000001;\n        if (note != null) bitField0_ |= 0x00000002;\n    }\n"
+        ));
+        assert!(cart.contains(
+            "        copy.bitField0_ = this.bitField0_;\n        copy.bitField0_ |= 0x00000001;\n        return copy;\n"
+        ));
+
+        // without a no-arg constructor, the copies go through the all-args one
+        let opts = JavaGenOptions {
+            no_arg_constructor: false,
+            ..opts
+        };
+        let cart = gen_file(proto, &opts, "shop/Cart.java");
+        assert!(cart.contains(
+            "        Cart copy = new Cart(count, this.note, this.tags);\n        copy.bitField0_ = this.bitField0_;\n        copy.bitField0_ |= 0x00000001;\n"
+        ));
+        assert!(cart.contains(
+            "            Cart result = new Cart(this.count, this.note, new ArrayList<>(this.tags));\n            result.bitField0_ = this.bitField0_;\n"
+        ));
+    }
+
+    #[test]
+    fn field_number_annotation_carries_tag() {
+        let opts = JavaGenOptions {
+            field_number_annotation: Some("com.example.serde.FieldNumber".into()),
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("import com.example.serde.FieldNumber;\n"));
+        // keyed by tag, not by name
+        assert!(item.contains("    @FieldNumber(1)\n    private String name;"));
+        assert!(item.contains("    @FieldNumber(2)\n    private Long count;"));
+        assert!(item.contains("    @FieldNumber(3)\n    private OrderItemType type;"));
+        assert!(item.contains("    @FieldNumber(4)\n    private double price_decimal;"));
+        assert!(item.contains("    @FieldNumber(5)\n    private long price_cents;"));
+    }
+
+    #[test]
+    fn jaxb_annotates_class_and_fields() {
+        let opts = JavaGenOptions {
+            jaxb: true,
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("import jakarta.xml.bind.annotation.*;\n"));
+        assert!(item.contains(
+            "@XmlRootElement\n@XmlAccessorType(XmlAccessType.FIELD)\npublic class OrderItem {"
+        ));
+        // element names keep the proto spelling
+        assert!(
+            item.contains("    @XmlElement(name = \"price_cents\")\n    private long price_cents;")
+        );
+        assert!(item.contains("    @XmlElement(name = \"name\")\n    private String name;"));
+    }
+
+    #[test]
+    fn sort_members_orders_fields_alphabetically() {
+        let opts = JavaGenOptions {
+            sort_members: true,
+            all_args_constructor: true,
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        let pos = |needle: &str| item.find(needle).unwrap();
+        let decls = [
+            "private Long count;",
+            "private String name;",
+            "private long price_cents;",
+            "private double price_decimal;",
+            "private OrderItemType type;",
+        ];
+        assert!(decls.windows(2).all(|w| pos(w[0]) < pos(w[1])));
+        assert!(item.contains(
+            "public OrderItem(Long count, String name, long price_cents, double price_decimal, OrderItemType type)"
+        ));
+        assert!(pos("getCount()") < pos("getName()"));
+
+        let item = gen_file(ORDER_PROTO, &JavaGenOptions::default(), "OrderItem.java");
+        assert!(item.find("private String name;") < item.find("private Long count;"));
+    }
+
+    #[test]
+    fn deeply_nested_messages_become_static_member_classes() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package deep;
+            message A {
+                B.C.D deepest = 1;
+                message B {
+                    message C {
+                        message D { string leaf = 1; B back = 2; }
+                    }
+                }
+            }
+            message Z { A.B.C.D d = 1; }
+        "#,
+        )
+        .unwrap();
+        let files = generate_java_from_model(&model);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["deep/A.java", "deep/Z.java"]);
+
+        let a = &files[0].1;
+        assert!(a.contains("public class A {\n    private A.B.C.D deepest;\n"));
+        assert!(a.contains("\n    public static class B {\n"));
+        assert!(a.contains("\n        public static class C {\n"));
+        assert!(a.contains("\n            public static class D {\n"));
+        assert!(a.contains("                private String leaf;\n"));
+        // enclosing member classes are in scope by simple name
+        assert!(a.contains("                private B back;\n"));
+        assert!(a.contains("                public D() {}\n"));
+        let z = &files[1].1;
+        assert!(z.contains("private A.B.C.D d;"));
+    }
+
+    #[test]
+    fn optional_getters_wrap_message_and_optional_fields() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+                Customer customer = 1;
+                optional int32 quantity = 2;
+                optional string note = 3;
+                Status status = 4;
+                string id = 5;
+                enum Status { NEW = 0; }
+            }
+            message Customer { string name = 1; }
+        "#;
+        let opts = JavaGenOptions {
+            optional_getters: true,
+            ..Default::default()
+        };
+        let order = gen_file(proto, &opts, "shop/Order.java");
+        assert!(order.contains("import java.util.Optional;\nimport java.util.OptionalInt;\n"));
+        assert!(order.contains(
+            "public Optional<Customer> getCustomer() { return Optional.ofNullable(this.customer); }"
+        ));
+        // optional primitives are stored boxed so null means unset
+        assert!(order.contains("private Integer quantity;"));
+        assert!(order.contains(
+            "public OptionalInt getQuantity() { return this.quantity == null ? OptionalInt.empty() : OptionalInt.of(this.quantity); }"
+        ));
+        assert!(order.contains("public Optional<String> getNote()"));
+        // implicit-presence scalars and enums keep plain getters
+        assert!(order.contains("public Status getStatus() { return this.status; }"));
+        assert!(order.contains("public String getId() { return this.id; }"));
+        assert!(order.contains("public void setCustomer(Customer value)"));
+    }
+
+    #[test]
+    fn field_accessor_map_references_each_getter() {
+        let opts = JavaGenOptions {
+            field_accessor_map: true,
+            ..Default::default()
+        };
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("import java.util.function.Function;\n"));
+        assert!(item.contains(
+            "    public static final Map<String, Function<OrderItem, Object>> FIELD_ACCESSORS;\n"
+        ));
+        for (name, getter) in [
+            ("name", "getName"),
+            ("count", "getCount"),
+            ("type", "getType"),
+            ("price_decimal", "getPrice_decimal"),
+            ("price_cents", "getPrice_cents"),
+        ] {
+            let put = format!(
+                "        accessors.put(\"{}\", OrderItem::{});\n",
+                name, getter
+            );
+            assert!(item.contains(&put), "missing accessor for {}", name);
+            assert!(item.contains(&format!(" {}() {{", getter)));
+        }
+        assert!(item.contains("FIELD_ACCESSORS = Collections.unmodifiableMap(accessors);"));
+    }
+
+    #[test]
+    fn enum_constants_carry_javadoc_and_deprecation() {
+        let proto = r#"
+            syntax = "proto3";
+            enum Color {
+                UNKNOWN = 0;
+                // Pure red.
+                RED = 1 [deprecated = true];
+                BLUE = 2;
+            }
+        "#;
+        let color = gen_file(proto, &JavaGenOptions::default(), "Color.java");
+        assert!(color.contains(
+            "    UNKNOWN(0),\n    /** Pure red. */\n    @Deprecated\n    RED(1),\n    BLUE(2);"
+        ));
+        assert_eq!(color.matches("@Deprecated").count(), 1);
+    }
+
+    #[test]
+    fn file_naming_strategy_controls_file_names() {
+        let model = parse_proto_file(ORDER_PROTO).unwrap();
+        let opts = JavaGenOptions {
+            file_naming: FileNaming::Suffix("Dto".into()),
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        assert!(paths.contains(&"me/alekseinovikov/proto/OrderDto.java"));
+        assert!(paths.contains(&"me/alekseinovikov/proto/OrderItemDto.java"));
+        assert!(!paths.contains(&"me/alekseinovikov/proto/Order.java"));
+
+        let opts = JavaGenOptions {
+            file_naming: FileNaming::Custom(|name| name.to_lowercase()),
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        assert!(
+            files
+                .iter()
+                .any(|(p, _)| p == "me/alekseinovikov/proto/orderitem.java")
+        );
+    }
+
+    #[test]
+    fn class_suffix_applies_to_names_files_and_references() {
+        let opts = JavaGenOptions {
+            class_suffix: "Dto".into(),
+            builder: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "OrderDto.java");
+        assert!(order.contains("public class OrderDto {"));
+        assert!(order.contains("private List<OrderItemDto> items;"));
+        assert!(order.contains("public OrderDto build()"));
+        assert!(!order.contains("List<OrderItem> "));
+
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+                Status status = 1;
+                enum Status { NEW = 0; }
+            }
+        "#,
+        )
+        .unwrap();
+        let opts = JavaGenOptions {
+            class_prefix: "Pb".into(),
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        assert_eq!(files[0].0, "PbOrder.java");
+        assert!(files[0].1.contains("public class PbOrder {"));
+        assert!(files[0].1.contains("private PbStatus status;"));
+        assert!(files[0].1.contains("public enum PbStatus {"));
+    }
+
+    #[test]
+    fn proto_bridge_copies_field_by_field() {
+        let opts = JavaGenOptions {
+            proto_bridge: Some(ProtoBridge {
+                package: "com.acme".into(),
+                class_suffix: "Proto".into(),
+            }),
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("import java.util.ArrayList;\n"));
+        assert!(order.contains("    public com.acme.OrderProto toProto() {\n"));
+        assert!(order.contains("        b.setId(this.id);\n"));
+        assert!(order.contains("        if (this.name != null) b.setName(this.name);\n"));
+        assert!(order.contains("                b.addItems(v.toProto());\n"));
+        assert!(order.contains(
+            "        if (this.status != null) b.setStatus(com.acme.OrderProto.Status.forNumber(this.status.getNumber()));\n"
+        ));
+        assert!(order.contains("    public static Order fromProto(com.acme.OrderProto p) {\n"));
+        assert!(order.contains("        for (com.acme.OrderItemProto v : p.getItemsList()) {\n"));
+        assert!(order.contains(
+            "        if (p.hasShippingAddress()) m.setShipping_address(Address.fromProto(p.getShippingAddress()));\n"
+        ));
+        assert!(order.contains("        m.setStatus(Status.valueOf(p.getStatus().name()));\n"));
+        // nested messages bridge to protobuf-java's nested classes
+        assert!(order.contains("        public com.acme.OrderProto.Address toProto() {\n"));
+
+        let item = gen_file(ORDER_PROTO, &opts, "OrderItem.java");
+        assert!(item.contains("        if (this.count != null) b.setCount(this.count);\n"));
+        assert!(item.contains("        if (p.hasCount()) m.setCount(p.getCount());\n"));
+        assert!(
+            item.contains("        if (p.hasPriceCents()) m.setPrice_cents(p.getPriceCents());\n")
+        );
+        assert!(
+            item.contains(
+                "        if (this.price_cents != 0L) b.setPriceCents(this.price_cents);\n"
+            )
+        );
+    }
+
+    #[test]
+    fn rename_package_pass_moves_generated_files() {
+        let mut model = parse_proto_file(ORDER_PROTO).unwrap();
+        use parser::ModelPass;
+        parser::RenamePackage {
+            from: "me.alekseinovikov.proto".into(),
+            to: "com.acme.orders".into(),
+        }
+        .apply(&mut model);
+        let files = generate_java_from_model(&model);
+        let (path, order) = &files[0];
+        assert_eq!(path, "com/acme/orders/Order.java");
+        assert!(order.contains("package com.acme.orders;\n"));
+        assert!(files.iter().all(|(p, _)| p.starts_with("com/acme/orders/")));
+    }
+
+    #[test]
+    fn bean_validation_annotates_constrained_fields() {
+        let proto = r#"
+            syntax = "proto2";
+            message Signup {
+                required string email = 1 [(validate.rules).string.email = true];
+                optional string name = 2 [(validate.rules).string.min_len = 1, (validate.rules).string.max_len = 64];
+                optional int32 age = 3 [(validate.rules).int32.gte = 18, (validate.rules).int32.lt = 130];
+                optional string code = 4 [(validate.rules).string.pattern = "^[A-Z]+\\d$"];
+                optional string note = 5;
+            }
+        "#;
+        let opts = JavaGenOptions {
+            bean_validation: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Signup.java");
+        assert!(src.contains("import jakarta.validation.constraints.*;\n"));
+        assert!(src.contains("    @NotNull\n    @Email\n    private String email;\n"));
+        assert!(src.contains("    @Size(min = 1, max = 64)\n    private String name;\n"));
+        assert!(src.contains("    @Min(18)\n    @Max(129)\n    private int age;\n"));
+        assert!(
+            src.contains("    @Pattern(regexp = \"^[A-Z]+\\\\d$\")\n    private String code;\n")
+        );
+        assert!(src.contains("    private String note;\n"));
+        assert!(!src.contains("@NotNull\n    private String note"));
+
+        let bounds = r#"
+            syntax = "proto3";
+            message Limits {
+                int64 above_max = 1 [(validate.rules).int64.gt = 9223372036854775807];
+                int64 below_min = 2 [(validate.rules).int64.lt = -9223372036854775808];
+                int64 big = 3 [(validate.rules).int64.gte = 3000000000, (validate.rules).int64.lt = 9223372036854775807];
+                string line = 4 [(validate.rules).string.pattern = "^a\r\n\tb\u0001$"];
+            }
+        "#;
+        let src = gen_file(bounds, &opts, "Limits.java");
+        // no long exceeds the exclusive bounds, so there is nothing to annotate
+        assert!(src.contains(
+            "public class Limits {\n    private long above_max;\n    private long below_min;\n"
+        ));
+        assert!(src.contains(
+            "    @Min(3000000000L)\n    @Max(9223372036854775806L)\n    private long big;\n"
+        ));
+        assert!(src.contains("    @Pattern(regexp = \"^a\\r\\n\\tb\\001$\")\n"));
+
+        let plain = gen_file(proto, &JavaGenOptions::default(), "Signup.java");
+        assert!(!plain.contains("@NotNull"));
+        assert!(!plain.contains("jakarta.validation"));
+    }
+
+    #[test]
+    fn enum_predicates_per_constant() {
+        let proto = "syntax = \"proto3\"; enum PaymentState { NEW = 0; PAID_IN_FULL = 1; }";
+        let opts = JavaGenOptions {
+            enum_predicates: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "PaymentState.java");
+        assert!(src.contains("    public boolean isNew() { return this == NEW; }\n"));
+        assert!(
+            src.contains("    public boolean isPaidInFull() { return this == PAID_IN_FULL; }\n")
+        );
+        assert!(
+            !gen_file(proto, &JavaGenOptions::default(), "PaymentState.java").contains("isNew()")
+        );
+    }
+
+    #[test]
+    fn package_mapping_longest_prefix_wins() {
+        let model = parser::parse_proto_str(
+            "syntax = \"proto3\"; package me.alekseinovikov.proto.billing; message Invoice { int64 id = 1; }",
+        )
+        .unwrap();
+        let opts = JavaGenOptions {
+            package_mapping: vec![
+                ("me.alekseinovikov".into(), "com.acme".into()),
+                (
+                    "me.alekseinovikov.proto".into(),
+                    "com.acme.generated".into(),
+                ),
+                ("me.alekseinovikov.pro".into(), "wrong.segment".into()),
+            ],
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&model, &opts);
+        assert_eq!(files[0].0, "com/acme/generated/billing/Invoice.java");
+        assert!(files[0].1.contains("package com.acme.generated.billing;\n"));
+    }
+
+    #[test]
+    fn topo_order_emits_dependencies_first() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order { repeated OrderItem items = 1; Customer customer = 2; }
+            message OrderItem { string sku = 1; }
+            message Node { Edge first = 1; }
+            message Customer { Node root = 1; }
+            message Edge { Node to = 1; }
+            "#,
+        )
+        .unwrap();
+        let names = |opts: &JavaGenOptions| -> Vec<String> {
+            generate_java_with_options(&model, opts)
+                .into_iter()
+                .map(|(path, _)| path.trim_end_matches(".java").to_string())
+                .collect()
+        };
+        let opts = JavaGenOptions {
+            topo_order: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&opts),
+            ["OrderItem", "Node", "Edge", "Customer", "Order"]
+        );
+        assert_eq!(
+            names(&JavaGenOptions::default()),
+            ["Order", "OrderItem", "Node", "Customer", "Edge"]
+        );
+    }
+
+    #[test]
+    fn unrecognized_enum_value_and_from_number() {
+        let proto = r#"
+            syntax = "proto3";
+            enum Status {
+              option allow_alias = true;
+              UNKNOWN = 0;
+              ACTIVE = 1;
+              RUNNING = 1;
+            }
+            "#;
+        let opts = JavaGenOptions {
+            unrecognized_enum_value: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Status.java");
+        assert!(src.contains("    RUNNING(1),\n    UNRECOGNIZED(-1);\n"));
+        assert!(src.contains(
+            "    public static Status fromNumber(int number) {\n        switch (number) {\n            case 0: return UNKNOWN;\n            case 1: return ACTIVE;\n            default: return UNRECOGNIZED;\n        }\n    }\n"
+        ));
+
+        let plain = gen_file(proto, &JavaGenOptions::default(), "Status.java");
+        assert!(plain.contains("    RUNNING(1);\n"));
+        assert!(!plain.contains("UNRECOGNIZED"));
+    }
+
+    #[test]
+    fn checkstyle_compatible_output() {
+        let proto = r#"
+            syntax = "proto3";
+            package p.order_id;
+            message Event {
+                int64 created_at = 1;
+                optional string note = 2;
+                string class_ = 3;
+                string order_id = 4;
+            }
+        "#;
+        let opts = JavaGenOptions {
+            checkstyle_compatible: true,
+            presence_bitset: true,
+            jackson: true,
+            builder: true,
+            ..Default::default()
+        };
+        let files = generate_java_with_options(&parser::parse_proto_str(proto).unwrap(), &opts);
+        let (path, src) = &files[0];
+        assert_eq!(path, "p/order_id/Event.java");
+        assert!(src.contains("package p.order_id;\n"));
+        assert!(src.contains("/** Message {@code Event}. */\npublic class Event {\n"));
+        assert!(src.contains(
+            "    /** Value of {@code created_at} (field 1). */\n    @JsonProperty(\"created_at\")\n    private long createdAt;\n"
+        ));
+        assert!(src.contains("    private int bitField0;\n"));
+        assert!(src.contains(
+            "    /**\n     * Sets {@code created_at} (field 1).\n     *\n     * @param value the new value\n     */\n    public void setCreatedAt(final long value) {\n        this.createdAt = value;\n    }\n\n    /**\n     * Returns {@code note} (field 2).\n     *\n     * @return the current value\n     */\n    public String getNote() {\n"
+        ));
+        assert!(src.contains(
+            "    /** Clears {@code note} (field 2). */\n    public void clearNote() {\n        this.note = null;\n        bitField0 &= ~0x00000001;\n    }\n"
+        ));
+        assert!(src.contains(
+            "        public Builder setOrderId(final String value) {\n            this.orderId = value;\n            return this;\n        }\n"
+        ));
+        // camelCase `class` would be a keyword (and getClass() is final)
+        assert!(src.contains("    private String class_;\n"));
+        assert!(src.contains("    public String getClass_() {\n"));
+        assert!(!src.contains("getClass()"));
+        assert!(!src.contains("\n\n\n"));
+        assert!(!src.contains("getCreated_at"));
+
+        let plain = gen_file(proto, &JavaGenOptions::default(), "p/order_id/Event.java");
+        assert!(plain.contains("    private long created_at;\n"));
+        assert!(!plain.contains("/**"));
+    }
+
+    #[test]
+    fn base_class_for_targeted_messages() {
+        let proto = r#"
+            syntax = "proto3";
+            message Header { string trace_id = 1; }
+            message Order { Header header = 1; message Line { int32 qty = 1; } }
+            message Refund { Header header = 1; }
+            "#;
+        let opts = JavaGenOptions {
+            base_class_for: HashMap::from([
+                ("Order".to_string(), "com.acme.AbstractEnvelope".to_string()),
+                (
+                    "Order.Line".to_string(),
+                    "com.acme.AbstractLine".to_string(),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let src = |name: &str| gen_file(proto, &opts, name);
+        assert!(
+            src("Order.java").contains("public class Order extends com.acme.AbstractEnvelope {\n")
+        );
+        assert!(
+            src("Order.java")
+                .contains("    public static class Line extends com.acme.AbstractLine {\n")
+        );
+        assert!(src("Refund.java").contains("public class Refund {\n"));
+        assert!(src("Header.java").contains("public class Header {\n"));
+    }
+
+    #[test]
+    fn repeated_nested_types_as_generic_arguments() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+              enum Status { NEW = 0; }
+              message Line { int32 qty = 1; }
+              repeated Status statuses = 1;
+            }
+            message Report {
+              repeated Order.Status statuses = 1;
+              repeated shop.Order.Line lines = 2;
+            }
+            "#;
+        let src = |name: &str| gen_file(proto, &JavaGenOptions::default(), name);
+        assert!(src("shop/Order.java").contains("    private List<Status> statuses;\n"));
+        let report = src("shop/Report.java");
+        assert!(report.contains("    private List<Order.Status> statuses;\n"));
+        assert!(report.contains("    private List<Order.Line> lines;\n"));
+        assert!(report.contains("    public void setStatuses(List<Order.Status> value)"));
+
+        let opts = JavaGenOptions {
+            flatten_nested: true,
+            ..Default::default()
+        };
+        let report = gen_file(proto, &opts, "shop/Report.java");
+        assert!(report.contains("    private List<OrderStatus> statuses;\n"));
+        assert!(report.contains("    private List<OrderLine> lines;\n"));
+    }
+
+    #[test]
+    fn map_fields_with_nested_value_types() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+              enum Status { NEW = 0; }
+              map<string, Status> by_region = 1;
+            }
+            message Report {
+              map<string, Order.Status> statuses = 1;
+              map<int64, shop.Order> orders = 2;
+            }
+            "#;
+        let order = gen_file(proto, &JavaGenOptions::default(), "shop/Order.java");
+        assert!(order.contains("import java.util.Map;\n"));
+        assert!(order.contains("    private Map<String, Status> by_region;\n"));
+        let report = gen_file(proto, &JavaGenOptions::default(), "shop/Report.java");
+        assert!(report.contains("    private Map<String, Order.Status> statuses;\n"));
+        assert!(report.contains("    private Map<Long, Order> orders;\n"));
+        assert!(report.contains("    public void setStatuses(Map<String, Order.Status> value)"));
+        assert!(!report.contains("import java.util.List;"));
+
+        let opts = JavaGenOptions {
+            explicit_defaults: true,
+            ..Default::default()
+        };
+        let report = gen_file(proto, &opts, "shop/Report.java");
+        assert!(report.contains("import java.util.HashMap;\n"));
+        assert!(
+            report.contains("    private Map<String, Order.Status> statuses = new HashMap<>();\n")
+        );
+    }
+
+    #[test]
+    fn map_entry_messages_are_not_generated() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+              message CountsEntry {
+                option map_entry = true;
+                string key = 1;
+                int32 value = 2;
+              }
+              message Line { int32 qty = 1; }
+              string id = 1;
+            }
+            message TagsEntry {
+              option map_entry = true;
+              string key = 1;
+              string value = 2;
+            }
+            "#,
+        )
+        .unwrap();
+        let files = generate_java_from_model(&model);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["Order.java"]);
+        assert!(files[0].1.contains("public static class Line {"));
+        assert!(!files[0].1.contains("CountsEntry"));
+
+        let opts = JavaGenOptions {
+            flatten_nested: true,
+            ..Default::default()
+        };
+        let flat: Vec<String> = generate_java_with_options(&model, &opts)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(flat, ["OrderLine.java", "Order.java"]);
+    }
+
+    #[test]
+    fn java_time_maps_well_known_types() {
+        let proto = r#"
+            syntax = "proto3";
+            package shop;
+            import "google/protobuf/duration.proto";
+            import "google/protobuf/timestamp.proto";
+            import "google/protobuf/wrappers.proto";
+            message Shipment {
+                google.protobuf.Timestamp shipped_at = 1;
+                google.protobuf.Duration transit = 2;
+                google.protobuf.StringValue carrier = 3;
+                repeated google.protobuf.Int32Value weights = 4;
+            }
+        "#;
+        let opts = JavaGenOptions {
+            java_time: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Shipment.java");
+        assert!(src.contains("import java.time.Duration;\nimport java.time.Instant;\n"));
+        assert!(src.contains("private Instant shipped_at;"));
+        assert!(src.contains("private Duration transit;"));
+        assert!(src.contains("private String carrier;"));
+        assert!(src.contains("private List<Integer> weights;"));
+
+        // without the option they stay plain message references
+        let src = gen_file(proto, &JavaGenOptions::default(), "Shipment.java");
+        assert!(src.contains("private Timestamp shipped_at;"));
+        assert!(!src.contains("java.time"));
+    }
+
+    #[test]
+    fn annotation_namespace_switches_import_prefixes() {
+        let proto = r#"
+            syntax = "proto2";
+            package shop;
+            message Order { required string id = 1; }
+        "#;
+        let render = |ns| {
+            let opts = JavaGenOptions {
+                jaxb: true,
+                bean_validation: true,
+                generated_annotation: true,
+                annotation_namespace: ns,
+                ..Default::default()
+            };
+            gen_file(proto, &opts, "Order.java")
+        };
+
+        let jakarta = render(AnnotationNamespace::Jakarta);
+        assert!(jakarta.contains(
+            "import jakarta.validation.constraints.*;\nimport jakarta.xml.bind.annotation.*;\n"
+        ));
+        assert!(!jakarta.contains("import javax."));
+        // the JDK's @Generated either way
+        assert!(jakarta.contains("@javax.annotation.processing.Generated(\"rs-proto-to-pojo\")\n"));
+
+        let javax = render(AnnotationNamespace::Javax);
+        assert!(javax.contains(
+            "import javax.validation.constraints.*;\nimport javax.xml.bind.annotation.*;\n"
+        ));
+        assert!(javax.contains("@javax.annotation.processing.Generated(\"rs-proto-to-pojo\")\n"));
+        assert!(!javax.contains("jakarta."));
+    }
+
+    #[test]
+    fn validate_on_build_checks_required_fields() {
+        let proto = r#"
+            syntax = "proto2";
+            package shop;
+            message Order {
+                required int64 id = 1;
+                required string customer = 2;
+                optional string note = 3;
+            }
+        "#;
+        let opts = JavaGenOptions {
+            builder: true,
+            validate_on_build: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Order.java");
+        assert!(src.contains("        private int bitField0_;\n"));
+        assert!(src.contains(
+            "public Builder setId(long value) { this.id = value; bitField0_ |= 0x00000001; return this; }"
+        ));
+        assert!(src.contains(
+            "        public Order build() {\n            if ((bitField0_ & 0x00000001) == 0) throw new IllegalStateException(\"required field `id` is not set\");\n            if (this.customer == null) throw new IllegalStateException(\"required field `customer` is not set\");\n            Order result"
+        ));
+        // the message tracks whether `id` was set, so
+        // `new Order().toBuilder().build()` still fails the check
+        assert!(src.contains("    private int bitField0_;\n"));
+        assert!(src.contains(
+            "    public void setId(long value) { this.id = value; bitField0_ |= 0x00000001; }\n"
+        ));
+        assert!(
+            src.contains(
+                "        builder.bitField0_ = this.bitField0_;\n        return builder;\n"
+            )
+        );
+        assert!(!src.contains(" = true;"));
+        assert!(!src.contains("`note`"));
+
+        // off by default
+        let opts = JavaGenOptions {
+            builder: true,
+            ..Default::default()
+        };
+        let src = gen_file(proto, &opts, "Order.java");
+        assert!(!src.contains("IllegalStateException"));
+    }
+
+    #[test]
+    fn strict_getters_throw_on_unset_message_fields() {
+        let opts = JavaGenOptions {
+            strict_getters: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains(
+            "    public Address getShipping_address() { if (this.shipping_address == null) throw new IllegalStateException(\"field shipping_address not set\"); return this.shipping_address; }\n    public boolean hasShipping_address() { return this.shipping_address != null; }\n"
+        ));
+        // scalars, enums and repeated fields keep plain getters
+        assert!(order.contains("    public int getId() { return this.id; }\n"));
+        assert!(order.contains("    public Status getStatus() { return this.status; }\n"));
+        assert!(order.contains("    public List<OrderItem> getItems() { return this.items; }\n"));
+        assert!(!order.contains("hasItems"));
+    }
+
+    #[test]
+    fn enum_number_map_backs_from_number() {
+        let opts = JavaGenOptions {
+            enum_number_map: true,
+            ..Default::default()
+        };
+        let item_type = gen_file(ORDER_PROTO, &opts, "OrderItemType.java");
+        assert!(item_type.contains("import java.util.HashMap;\nimport java.util.Map;\n"));
+        assert!(item_type.contains(
+            "    private static final Map<Integer, OrderItemType> BY_NUMBER = new HashMap<>();\n    static {\n        for (OrderItemType value : values()) {\n            BY_NUMBER.putIfAbsent(value.number, value);\n        }\n    }\n"
+        ));
+        assert!(item_type.contains(
+            "    public static OrderItemType fromNumber(int number) { return BY_NUMBER.get(number); }\n"
+        ));
+
+        // nested enums bring their imports into the enclosing class
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains("import java.util.HashMap;\n"));
+        assert!(order.contains("private static final Map<Integer, Status> BY_NUMBER"));
+
+        let opts = JavaGenOptions {
+            enum_number_map: true,
+            unrecognized_enum_value: true,
+            ..Default::default()
+        };
+        let item_type = gen_file(ORDER_PROTO, &opts, "OrderItemType.java");
+        assert!(item_type.contains("            if (value == UNRECOGNIZED) continue;\n"));
+        assert!(item_type.contains("return BY_NUMBER.getOrDefault(number, UNRECOGNIZED); }"));
+        assert!(!item_type.contains("switch"));
+    }
+
+    #[test]
+    fn withers_copy_all_other_fields() {
+        let opts = JavaGenOptions {
+            withers: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains(
+            "    public Order withName(String name) {\n        Order copy = new Order();\n        copy.id = this.id;\n        copy.name = name;\n        copy.items = this.items;\n        copy.shipping_address = this.shipping_address;\n        copy.status = this.status;\n        return copy;\n    }\n"
+        ));
+        assert!(order.contains("    public Order withItems(List<OrderItem> items) {\n"));
+
+        // without a no-arg constructor the all-args one does the copy
+        let opts = JavaGenOptions {
+            withers: true,
+            no_arg_constructor: false,
+            all_args_constructor: true,
+            ..Default::default()
+        };
+        let order = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(order.contains(
+            "    public Order withName(String name) {\n        return new Order(this.id, name, this.items, this.shipping_address, this.status);\n    }\n"
+        ));
+    }
 }
@@ -0,0 +1,104 @@
+use parser::{Message, TypeDecl};
+
+use crate::immutables::any_field;
+use crate::{
+    JavaGenOptions, TypeScope, checkstyle, collect_type_imports, indent, is_map, is_repeated,
+    java_class_name, java_field_type, nested_enum_imports, render_all_args_constructor,
+    render_enum_decl, render_imports, render_package_line,
+};
+
+// Protobuf-lite style class for size-sensitive targets: public final fields
+// set by a single all-args constructor, and nothing else.
+pub(crate) fn render_lite_class(scope: &TypeScope, m: &Message, opts: &JavaGenOptions) -> String {
+    let mut imports = Vec::new();
+    if any_field(scope.model, m, opts, is_repeated) {
+        imports.push("java.util.List");
+    }
+    if any_field(scope.model, m, opts, is_map) {
+        imports.push("java.util.Map");
+    }
+    imports.extend(nested_enum_imports(scope.model, m, opts));
+    let mut type_imports = Vec::new();
+    collect_type_imports(scope, m, opts, &mut type_imports);
+    imports.extend(type_imports.iter().map(String::as_str));
+
+    let mut s = String::new();
+    s.push_str(&render_package_line(scope.java_package().as_deref()));
+    s.push_str(&render_imports(&imports));
+    s.push_str(&render_lite_decl(scope, m, opts, "public final class"));
+    s
+}
+
+fn render_lite_decl(scope: &TypeScope, m: &Message, opts: &JavaGenOptions, kind: &str) -> String {
+    let class = java_class_name(&m.name, opts);
+    let mut s = checkstyle::doc(opts, "", &format!("Message {{@code {}}}.", m.name));
+    s.push_str(&format!("{} {} {{\n", kind, class));
+    for f in &m.fields {
+        s.push_str(&checkstyle::doc(
+            opts,
+            "    ",
+            &format!("Value of {}.", checkstyle::field_ref(f)),
+        ));
+        s.push_str(&format!(
+            "    public final {} {};\n",
+            java_field_type(f, scope),
+            scope.member(f)
+        ));
+    }
+    if !m.fields.is_empty() {
+        s.push('\n');
+    }
+    let ctor = if m.fields.is_empty() {
+        checkstyle::doc(opts, "    ", "Creates an empty instance.")
+            + &format!("    public {}() {{}}\n", class)
+    } else {
+        render_all_args_constructor(m, &class, opts, scope, &[])
+    };
+    s.push_str(ctor.trim_end_matches('\n'));
+    s.push('\n');
+    if !opts.flatten_nested {
+        for t in scope.model.nested_types(&m.name) {
+            let decl = match t {
+                TypeDecl::Message(nested) => {
+                    let nested_scope = TypeScope {
+                        message: &nested.name,
+                        ..*scope
+                    };
+                    render_lite_decl(&nested_scope, nested, opts, "public static final class")
+                }
+                TypeDecl::Enum(e) => render_enum_decl(e, opts),
+            };
+            s.push('\n');
+            s.push_str(&indent(&decl));
+        }
+    }
+    s.push_str("}\n");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::JavaGenOptions;
+    use crate::tests::{ORDER_PROTO, gen_file};
+
+    #[test]
+    fn renders_public_final_fields_and_one_constructor() {
+        let opts = JavaGenOptions {
+            lite: true,
+            ..Default::default()
+        };
+        let src = gen_file(ORDER_PROTO, &opts, "Order.java");
+        assert!(src.contains("public final class Order {\n    public final int id;\n    public final String name;\n    public final List<OrderItem> items;\n"));
+        assert!(src.contains(
+            "    public Order(int id, String name, List<OrderItem> items, Address shipping_address, Status status) {\n        this.id = id;\n"
+        ));
+        assert_eq!(src.matches("public Order(").count(), 1);
+        assert!(src.contains(
+            "    public static final class Address {\n        public final String street;\n"
+        ));
+        assert!(!src.contains("\n    private "));
+        assert!(!src.contains("getId"));
+        assert!(!src.contains("setId"));
+        assert!(!src.contains("toString"));
+    }
+}
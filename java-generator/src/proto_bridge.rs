@@ -0,0 +1,287 @@
+use parser::{Field, FieldLabel, FieldType, Message, ScalarType, TypeDecl};
+
+use crate::{
+    JavaGenOptions, ProtoBridge, TypeScope, boxed, capitalize, checkstyle, is_repeated,
+    java_type_for, scalar_default, stored_boxed,
+};
+
+// How a field's value converts between the POJO and protobuf-java.
+enum Conversion {
+    Message(String), // protobuf-java class of the message
+    Enum(String),    // protobuf-java class of the enum
+    Bytes,           // byte[] <-> ByteString
+    Plain,           // same Java type on both sides
+}
+
+const BYTE_STRING: &str = "com.google.protobuf.ByteString";
+
+// `toProto()`/`fromProto()` copying field by field through the setters and
+// getters of both sides. Assumes a parallel protobuf-java class per message.
+pub(crate) fn render_proto_bridge(
+    scope: &TypeScope,
+    m: &Message,
+    class: &str,
+    bridge: &ProtoBridge,
+    opts: &JavaGenOptions,
+    presence: &[Option<usize>],
+) -> String {
+    let proto = proto_class(bridge, &m.name);
+    let mut s = checkstyle::doc(
+        opts,
+        "    ",
+        &format!(
+            "Copies this message into protobuf-java's.\n\n@return a new {{@code {}}}",
+            proto
+        ),
+    );
+    s.push_str(&format!("    public {} toProto() {{\n", proto));
+    s.push_str(&format!(
+        "        {0}.Builder b = {0}.newBuilder();\n",
+        proto
+    ));
+    for (f, bit) in m.fields.iter().zip(presence) {
+        s.push_str(&to_proto_field(scope, bridge, f, bit.is_some()));
+    }
+    s.push_str("        return b.build();\n");
+    s.push_str("    }\n\n");
+
+    s.push_str(&checkstyle::doc(
+        opts,
+        "    ",
+        &format!(
+            "Copies a protobuf-java message.\n\n@param p the message to copy\n@return a new {{@code {}}}",
+            class
+        ),
+    ));
+    s.push_str(&format!(
+        "    public static {} fromProto({}) {{\n",
+        class,
+        checkstyle::param(opts, &proto, "p")
+    ));
+    s.push_str(&format!("        {0} m = new {0}();\n", class));
+    for f in &m.fields {
+        s.push_str(&from_proto_field(scope, bridge, f));
+    }
+    s.push_str("        return m;\n");
+    s.push_str("    }\n\n");
+    s
+}
+
+fn to_proto_field(scope: &TypeScope, bridge: &ProtoBridge, f: &Field, has_bit: bool) -> String {
+    let accessor = proto_accessor(&f.name);
+    let member = scope.member(f);
+    let conversion = conversion(scope, bridge, &f.ty);
+    if is_repeated(f) {
+        if let Conversion::Plain = conversion {
+            return format!(
+                "        if (this.{0} != null) b.addAll{1}(this.{0});\n",
+                member, accessor
+            );
+        }
+        let et = java_type_for(&f.ty, scope);
+        return format!(
+            "        if (this.{0} != null) {{\n            for ({1} v : this.{0}) {{\n                b.add{2}({3});\n            }}\n        }}\n",
+            member,
+            et,
+            accessor,
+            to_proto_value(&conversion, "v")
+        );
+    }
+    if let FieldType::Map { key, value } = &f.ty {
+        if let Conversion::Plain = conversion {
+            return format!(
+                "        if (this.{0} != null) b.putAll{1}(this.{0});\n",
+                member, accessor
+            );
+        }
+        return format!(
+            "        if (this.{0} != null) {{\n            for (Map.Entry<{1}, {2}> e : this.{0}.entrySet()) {{\n                b.put{3}(e.getKey(), {4});\n            }}\n        }}\n",
+            member,
+            map_key_type(scope, key),
+            boxed(&java_type_for(value, scope)),
+            accessor,
+            to_proto_value(&conversion, "e.getValue()")
+        );
+    }
+    let value = to_proto_value(&conversion, &format!("this.{}", member));
+    let set = format!("b.set{}({});", accessor, value);
+    let nullable = stored_boxed(f, scope)
+        || matches!(
+            f.ty,
+            FieldType::Custom(_)
+                | FieldType::Message(_)
+                | FieldType::Enum(_)
+                | FieldType::Scalar(ScalarType::String | ScalarType::Bytes)
+        );
+    if nullable {
+        format!("        if (this.{} != null) {}\n", member, set)
+    } else if has_bit {
+        format!("        if (has{}()) {}\n", capitalize(&member), set)
+    } else if let (Some(_), FieldType::Scalar(st)) = (&f.oneof, &f.ty) {
+        // setting any member selects it, so skip unset (default) primitives
+        format!(
+            "        if (this.{} != {}) {}\n",
+            member,
+            scalar_default(st),
+            set
+        )
+    } else {
+        format!("        {}\n", set)
+    }
+}
+
+fn from_proto_field(scope: &TypeScope, bridge: &ProtoBridge, f: &Field) -> String {
+    let accessor = proto_accessor(&f.name);
+    let member = scope.member(f);
+    let setter = format!("m.set{}", capitalize(&member));
+    let conversion = conversion(scope, bridge, &f.ty);
+    if is_repeated(f) {
+        if let Conversion::Plain = conversion {
+            return format!(
+                "        {}(new ArrayList<>(p.get{}List()));\n",
+                setter, accessor
+            );
+        }
+        let et = java_type_for(&f.ty, scope);
+        let proto_et = match &conversion {
+            Conversion::Message(c) | Conversion::Enum(c) => c.as_str(),
+            _ => BYTE_STRING,
+        };
+        let list = format!("{}List", member);
+        let mut s = format!("        List<{}> {} = new ArrayList<>();\n", et, list);
+        s.push_str(&format!(
+            "        for ({} v : p.get{}List()) {{\n",
+            proto_et, accessor
+        ));
+        s.push_str(&format!(
+            "            {}.add({});\n",
+            list,
+            from_proto_value(&conversion, &et, "v")
+        ));
+        s.push_str("        }\n");
+        s.push_str(&format!("        {}({});\n", setter, list));
+        return s;
+    }
+    if let FieldType::Map { key, value } = &f.ty {
+        if let Conversion::Plain = conversion {
+            return format!(
+                "        {}(new HashMap<>(p.get{}Map()));\n",
+                setter, accessor
+            );
+        }
+        let (kt, vt) = (
+            map_key_type(scope, key),
+            boxed(&java_type_for(value, scope)),
+        );
+        let proto_vt = match &conversion {
+            Conversion::Message(c) | Conversion::Enum(c) => c.as_str(),
+            _ => BYTE_STRING,
+        };
+        let map = format!("{}Map", member);
+        let mut s = format!("        Map<{}, {}> {} = new HashMap<>();\n", kt, vt, map);
+        s.push_str(&format!(
+            "        for (Map.Entry<{}, {}> e : p.get{}Map().entrySet()) {{\n",
+            kt, proto_vt, accessor
+        ));
+        s.push_str(&format!(
+            "            {}.put(e.getKey(), {});\n",
+            map,
+            from_proto_value(&conversion, &vt, "e.getValue()")
+        ));
+        s.push_str("        }\n");
+        s.push_str(&format!("        {}({});\n", setter, map));
+        return s;
+    }
+    let et = java_type_for(&f.ty, scope);
+    let value = from_proto_value(&conversion, &et, &format!("p.get{}()", accessor));
+    // protobuf-java only has hasX() for fields with explicit presence
+    let has_presence = f.oneof.is_some()
+        || matches!(f.label, Some(FieldLabel::Optional | FieldLabel::Required))
+        || matches!(conversion, Conversion::Message(_));
+    if has_presence {
+        format!("        if (p.has{}()) {}({});\n", accessor, setter, value)
+    } else {
+        format!("        {}({});\n", setter, value)
+    }
+}
+
+fn map_key_type(scope: &TypeScope, key: &ScalarType) -> String {
+    boxed(&java_type_for(&FieldType::Scalar(key.clone()), scope))
+}
+
+fn to_proto_value(conversion: &Conversion, expr: &str) -> String {
+    match conversion {
+        Conversion::Message(_) => format!("{}.toProto()", expr),
+        Conversion::Enum(proto) => format!("{}.forNumber({}.getNumber())", proto, expr),
+        Conversion::Bytes => format!("{}.copyFrom({})", BYTE_STRING, expr),
+        Conversion::Plain => expr.to_string(),
+    }
+}
+
+fn from_proto_value(conversion: &Conversion, java_type: &str, expr: &str) -> String {
+    match conversion {
+        Conversion::Message(_) => format!("{}.fromProto({})", java_type, expr),
+        Conversion::Enum(_) => format!("{}.valueOf({}.name())", java_type, expr),
+        Conversion::Bytes => format!("{}.toByteArray()", expr),
+        Conversion::Plain => expr.to_string(),
+    }
+}
+
+fn conversion(scope: &TypeScope, bridge: &ProtoBridge, ty: &FieldType) -> Conversion {
+    let name = match ty {
+        FieldType::Scalar(ScalarType::Bytes) => return Conversion::Bytes,
+        FieldType::Scalar(_) => return Conversion::Plain,
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => name,
+        FieldType::Map { value, .. } => return conversion(scope, bridge, value),
+    };
+    // Types outside this file are assumed to be messages
+    let Some(local) = scope.model.resolve_type_name(scope.message, name) else {
+        let simple = name.rsplit('.').next().unwrap_or(name);
+        return Conversion::Message(proto_class(bridge, simple));
+    };
+    let is_enum = scope
+        .model
+        .types
+        .iter()
+        .any(|t| matches!(t, TypeDecl::Enum(e) if e.name == local));
+    if is_enum {
+        Conversion::Enum(proto_class(bridge, &local))
+    } else {
+        Conversion::Message(proto_class(bridge, &local))
+    }
+}
+
+// `Order.Address` -> `com.acme.OrderProto.Address`: protobuf-java nests
+// member classes, so only the top-level name takes the suffix.
+fn proto_class(bridge: &ProtoBridge, declared: &str) -> String {
+    let (top, nested) = match declared.split_once('.') {
+        Some((top, rest)) => (top, format!(".{}", rest)),
+        None => (declared, String::new()),
+    };
+    let class = format!("{}{}{}", top, bridge.class_suffix, nested);
+    if bridge.package.is_empty() {
+        class
+    } else {
+        format!("{}.{}", bridge.package, class)
+    }
+}
+
+// protobuf-java accessor casing: `_` is dropped and the next letter is
+// upper-cased, as is a letter following a digit (`price_cents` -> `PriceCents`).
+fn proto_accessor(name: &str) -> String {
+    let mut s = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+            continue;
+        }
+        if upper {
+            s.extend(c.to_uppercase());
+        } else {
+            s.push(c);
+        }
+        upper = c.is_ascii_digit();
+    }
+    s
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use parser::{ProtoFile, ProtoModel, TypeDecl, visible_files};
+
+/// Maps fully-qualified proto type names (e.g. `shop.common.Money`) to the
+/// Java class generated for them. Built from every file of a multi-file
+/// load so cross-package references can be emitted fully qualified.
+#[derive(Debug, Clone, Default)]
+pub struct TypeRegistry {
+    types: HashMap<String, JavaTypeRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaTypeRef {
+    pub package: Option<String>,
+    pub class_name: String, // proto name within the package, e.g. `Order.Address`
+}
+
+impl JavaTypeRef {
+    pub fn qualified_name(&self) -> String {
+        match &self.package {
+            Some(p) => format!("{}.{}", p, self.class_name),
+            None => self.class_name.clone(),
+        }
+    }
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_models<'a, I: IntoIterator<Item = &'a ProtoModel>>(models: I) -> Self {
+        let mut registry = Self::new();
+        for m in models {
+            registry.register_model(m);
+        }
+        registry
+    }
+
+    /// Registry of only the types visible from `path` per import semantics:
+    /// its own, its imports', and those re-exported via `import public`.
+    pub fn for_file(files: &[ProtoFile], path: &str) -> Self {
+        Self::from_models(visible_files(files, path).into_iter().map(|f| &f.model))
+    }
+
+    pub fn register_model(&mut self, model: &ProtoModel) {
+        for t in &model.types {
+            let name = match t {
+                TypeDecl::Message(m) => &m.name,
+                TypeDecl::Enum(e) => &e.name,
+            };
+            let fq = match &model.package {
+                Some(p) => format!("{}.{}", p, name),
+                None => name.clone(),
+            };
+            self.types.insert(
+                fq,
+                JavaTypeRef {
+                    package: model.package.clone(),
+                    class_name: name.clone(),
+                },
+            );
+        }
+    }
+
+    pub fn get(&self, fq_name: &str) -> Option<&JavaTypeRef> {
+        self.types.get(fq_name)
+    }
+
+    // protoc scoping: try `name` relative to the innermost scope first, then
+    // each enclosing scope, ending with `name` as a fully-qualified reference.
+    pub(crate) fn resolve(
+        &self,
+        package: Option<&str>,
+        scope: &str,
+        name: &str,
+    ) -> Option<&JavaTypeRef> {
+        let full_scope = [package.unwrap_or(""), scope]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(".");
+        let mut current = Some(full_scope.as_str()).filter(|s| !s.is_empty());
+        while let Some(s) = current {
+            if let Some(r) = self.get(&format!("{}.{}", s, name)) {
+                return Some(r);
+            }
+            current = s.rfind('.').map(|i| &s[..i]);
+        }
+        self.get(name)
+    }
+}
@@ -0,0 +1,154 @@
+use parser::{FieldType, RpcMethod, Service};
+
+use crate::{
+    JavaGenOptions, TypeScope, checkstyle, java_type_for, render_imports, render_package_line,
+    type_import,
+};
+
+// Plain Java interface mirroring a proto service: one method per rpc.
+pub(crate) fn render_service_interface(
+    scope: &TypeScope,
+    svc: &Service,
+    opts: &JavaGenOptions,
+) -> String {
+    let streams = svc
+        .methods
+        .iter()
+        .any(|m| m.client_streaming || m.server_streaming);
+    let mut imports = Vec::new();
+    if streams && opts.stream_type.contains('.') {
+        imports.push(opts.stream_type.as_str());
+    }
+    let type_imports: Vec<String> = svc
+        .methods
+        .iter()
+        .flat_map(|m| [&m.input_type, &m.output_type])
+        .filter_map(|name| type_import(name, scope))
+        .collect();
+    imports.extend(type_imports.iter().map(String::as_str));
+
+    let mut s = String::new();
+    s.push_str(&render_package_line(scope.java_package().as_deref()));
+    s.push_str(&render_imports(&imports));
+    s.push_str(&checkstyle::doc(
+        opts,
+        "",
+        &format!("Service {{@code {}}}.", svc.name),
+    ));
+    s.push_str(&format!("public interface {} {{\n", svc.name));
+    for m in &svc.methods {
+        s.push_str(&render_rpc_method(m, opts, scope));
+    }
+    s.push_str("}\n");
+    s
+}
+
+fn render_rpc_method(m: &RpcMethod, opts: &JavaGenOptions, scope: &TypeScope) -> String {
+    let stream = simple_name(&opts.stream_type);
+    let input = java_type_for(&FieldType::Custom(m.input_type.clone()), scope);
+    let output = java_type_for(&FieldType::Custom(m.output_type.clone()), scope);
+    let (param_type, param_name) = if m.client_streaming {
+        (format!("{}<{}>", stream, input), "requests")
+    } else {
+        (input, "request")
+    };
+    let return_type = if m.server_streaming {
+        format!("{}<{}>", stream, output)
+    } else {
+        output
+    };
+    let doc = checkstyle::doc(
+        opts,
+        "    ",
+        &format!(
+            "Rpc {{@code {}}}.\n\n@param {} the {}\n@return the {}",
+            m.name,
+            param_name,
+            if m.client_streaming {
+                "request stream"
+            } else {
+                "request"
+            },
+            if m.server_streaming {
+                "response stream"
+            } else {
+                "response"
+            }
+        ),
+    );
+    format!(
+        "{}    {} {}({} {});\n",
+        doc,
+        return_type,
+        lower_first(&m.name),
+        param_type,
+        param_name
+    )
+}
+
+fn simple_name(fq: &str) -> &str {
+    fq.rsplit('.').next().unwrap_or(fq)
+}
+
+fn lower_first(s: &str) -> String {
+    let mut it = s.chars();
+    match it.next() {
+        None => String::new(),
+        Some(first) => first.to_lowercase().collect::<String>() + it.as_str(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReferenceStyle;
+
+    #[test]
+    fn renders_unary_and_streaming_methods() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            service OrderService {
+                rpc GetOrder (OrderRequest) returns (OrderResponse);
+                rpc WatchOrders (OrderRequest) returns (stream OrderResponse);
+                rpc Upload (stream OrderRequest) returns (OrderResponse);
+            }
+        "#,
+        )
+        .unwrap();
+        let files = crate::generate_java_from_model(&model);
+        let (path, src) = files
+            .iter()
+            .find(|(p, _)| p == "shop/OrderService.java")
+            .expect("service interface generated");
+        assert_eq!(path, "shop/OrderService.java");
+        assert!(src.contains("public interface OrderService {"));
+        assert!(src.contains("    OrderResponse getOrder(OrderRequest request);"));
+        assert!(src.contains("import java.util.stream.Stream;"));
+        assert!(src.contains("Stream<OrderResponse> watchOrders(OrderRequest request);"));
+        assert!(src.contains("OrderResponse upload(Stream<OrderRequest> requests);"));
+
+        let opts = JavaGenOptions {
+            stream_type: "reactor.core.publisher.Flux".into(),
+            ..Default::default()
+        };
+        let scope = TypeScope {
+            package: Some("shop"),
+            message: "",
+            model: &model,
+            registry: None,
+            flatten_nested: false,
+            optional_getters: false,
+            class_prefix: "",
+            class_suffix: "",
+            reference_style: ReferenceStyle::FullyQualified,
+            package_mapping: &[],
+            java_time: false,
+            camel_case_members: false,
+        };
+        let src = render_service_interface(&scope, &model.services[0], &opts);
+        assert!(src.contains("import reactor.core.publisher.Flux;"));
+        assert!(src.contains("Flux<OrderResponse> watchOrders(OrderRequest request);"));
+    }
+}
@@ -1,7 +1,12 @@
 use std::collections::HashMap;
+use std::fs;
 use std::path::Path;
 
-use java_generator::generate_java_from_proto;
+use java_generator::{
+    JavaGenOptions, TypeFilter, generate_java_from_proto, generate_java_with_options,
+    write_java_to_dir_incremental,
+};
+use parser::parse_proto_file;
 
 #[test]
 fn e2e_generate_complex_proto() {
@@ -96,3 +101,50 @@ fn e2e_generate_complex_proto() {
     assert!(item.contains("private int quantity;"));
     assert!(item.contains("private double price;"));
 }
+
+#[test]
+fn e2e_type_filter_enums_only() {
+    let model = parser::parse_proto_file("tests/resources/complex.proto").unwrap();
+    let opts = JavaGenOptions {
+        type_filter: TypeFilter::EnumsOnly,
+        ..Default::default()
+    };
+    let files = generate_java_with_options(&model, &opts);
+    let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(paths, ["com/example/shop/OrderStatus.java"]);
+    assert!(files[0].1.contains("public enum OrderStatus"));
+
+    let opts = JavaGenOptions {
+        type_filter: TypeFilter::MessagesOnly,
+        ..Default::default()
+    };
+    let files = generate_java_with_options(&model, &opts);
+    assert_eq!(files.len(), 4);
+    assert!(files.iter().all(|(_, src)| src.contains("public class ")));
+}
+
+#[test]
+fn e2e_incremental_write_skips_unchanged_files() {
+    let out = Path::new("target/tmp/java-incremental");
+    let _ = fs::remove_dir_all(out);
+    let model = parse_proto_file("tests/resources/complex.proto").unwrap();
+    let opts = JavaGenOptions::default();
+
+    let first = write_java_to_dir_incremental(&model, out, &opts).unwrap();
+    assert!(!first.is_empty());
+    let second = write_java_to_dir_incremental(&model, out, &opts).unwrap();
+    assert!(second.is_empty(), "rewrote {:?}", second);
+
+    // only files whose content changes are rewritten
+    let builder = JavaGenOptions {
+        builder: true,
+        ..Default::default()
+    };
+    let third = write_java_to_dir_incremental(&model, out, &builder).unwrap();
+    let with_builder = generate_java_with_options(&model, &builder)
+        .into_iter()
+        .filter(|(_, code)| code.contains("class Builder"))
+        .count();
+    assert_eq!(third.len(), with_builder);
+    assert!(third.len() < first.len());
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+
+use java_generator::{
+    JavaGenOptions, MANIFEST_FILE, ReferenceStyle, TypeRegistry, generate_java_from_dir,
+    generate_java_with_registry,
+};
+use parser::load_proto_tree;
+
+#[test]
+fn e2e_cross_package_types_are_fully_qualified() {
+    let files = load_proto_tree("tests/resources/multi", "orders/order.proto")
+        .expect("load should succeed");
+    assert_eq!(
+        files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+        vec!["orders/order.proto", "common/money.proto"]
+    );
+
+    let registry = TypeRegistry::from_models(files.iter().map(|f| &f.model));
+    let out = generate_java_with_registry(&files[0].model, &JavaGenOptions::default(), &registry);
+    let order = &out
+        .iter()
+        .find(|(p, _)| p == "shop/orders/Order.java")
+        .expect("Order generated")
+        .1;
+
+    // Imported types resolve to their own Java package
+    assert!(order.contains("private shop.common.Money total;"));
+    assert!(order.contains("private shop.common.Money tax;"));
+    // Same-package types keep their simple name
+    assert!(order.contains("private Line line;"));
+
+    let line = &out
+        .iter()
+        .find(|(p, _)| p == "shop/orders/Line.java")
+        .unwrap()
+        .1;
+    assert!(line.contains("private shop.common.Money price;"));
+}
+
+#[test]
+fn e2e_cross_package_reference_styles() {
+    let files = load_proto_tree("tests/resources/multi", "orders/order.proto").unwrap();
+    let registry = TypeRegistry::from_models(files.iter().map(|f| &f.model));
+    let order = |style| {
+        let opts = JavaGenOptions {
+            reference_style: style,
+            ..Default::default()
+        };
+        generate_java_with_registry(&files[0].model, &opts, &registry)
+            .into_iter()
+            .find(|(p, _)| p == "shop/orders/Order.java")
+            .expect("Order generated")
+            .1
+    };
+
+    let qualified = order(ReferenceStyle::FullyQualified);
+    assert!(!qualified.contains("import shop.common.Money;"));
+    assert!(qualified.contains("private shop.common.Money total;"));
+
+    let imported = order(ReferenceStyle::Import);
+    assert!(imported.contains("package shop.orders;\n\nimport shop.common.Money;\n\n"));
+    assert!(imported.contains("private Money total;"));
+    assert!(imported.contains("private Money tax;"));
+    assert!(imported.contains("private Line line;"));
+    assert!(!imported.contains("shop.common.Money total"));
+}
+
+#[test]
+fn e2e_generate_from_directory() {
+    let out = Path::new("target/tmp/java-from-dir");
+    let _ = fs::remove_dir_all(out);
+
+    let written = generate_java_from_dir("tests/resources/multi", out, &JavaGenOptions::default())
+        .expect("generation should succeed");
+
+    let mut rel: Vec<String> = written
+        .iter()
+        .map(|p| {
+            p.strip_prefix(out)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    rel.sort();
+    assert_eq!(
+        rel,
+        vec![
+            "shop/common/Money.java",
+            "shop/orders/Line.java",
+            "shop/orders/Order.java"
+        ]
+    );
+
+    let order = fs::read_to_string(out.join("shop/orders/Order.java")).unwrap();
+    assert!(order.contains("private shop.common.Money total;"));
+}
+
+#[test]
+fn e2e_manifest_lists_written_files() {
+    let out = Path::new("target/tmp/java-manifest");
+    let _ = fs::remove_dir_all(out);
+    let opts = JavaGenOptions {
+        manifest: true,
+        ..Default::default()
+    };
+
+    let written = generate_java_from_dir("tests/resources/multi", out, &opts)
+        .expect("generation should succeed");
+
+    let manifest = fs::read_to_string(out.join(MANIFEST_FILE)).unwrap();
+    let mut lines: Vec<&str> = manifest.lines().collect();
+    lines.sort_unstable();
+    assert_eq!(
+        lines,
+        [
+            "shop/common/Money.java\tcommon/money.proto",
+            "shop/orders/Line.java\torders/order.proto",
+            "shop/orders/Order.java\torders/order.proto",
+        ]
+    );
+    assert_eq!(lines.len(), written.len());
+    for line in lines {
+        let rel = line.split('\t').next().unwrap();
+        assert!(written.contains(&out.join(rel)), "{} not written", rel);
+    }
+}
+
+#[test]
+fn e2e_public_imports_are_re_exported() {
+    let files =
+        load_proto_tree("tests/resources/public_import", "c.proto").expect("load should succeed");
+    // the weak import of a missing file is skipped
+    let mut paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["a.proto", "b.proto", "c.proto", "d.proto"]);
+
+    let registry = TypeRegistry::for_file(&files, "c.proto");
+    // b.proto reaches c.proto through a.proto's `import public`
+    assert!(registry.get("demo.b.Thing").is_some());
+    assert!(registry.get("demo.a.Wrapper").is_some());
+    // a plain import is not re-exported
+    assert!(registry.get("demo.d.Private").is_none());
+
+    let out = generate_java_with_registry(&files[0].model, &JavaGenOptions::default(), &registry);
+    let user = &out
+        .iter()
+        .find(|(p, _)| p == "demo/c/User.java")
+        .expect("User generated")
+        .1;
+    assert!(user.contains("private demo.b.Thing thing;"));
+    assert!(user.contains("private demo.a.Wrapper wrapper;"));
+}
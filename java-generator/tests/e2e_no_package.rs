@@ -0,0 +1,33 @@
+use java_generator::{
+    JavaGenOptions, TypeRegistry, generate_java_checked, generate_java_with_registry,
+};
+use parser::parse_proto_file;
+
+#[test]
+fn e2e_no_package_types_generate_at_root() {
+    let model = parse_proto_file("tests/resources/no_package.proto").expect("parse");
+    assert_eq!(model.package, None);
+
+    // mutual references resolve, so validation passes
+    let out = generate_java_checked(&model, &JavaGenOptions::default()).expect("valid model");
+    let mut paths: Vec<&str> = out.iter().map(|(p, _)| p.as_str()).collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["Edge.java", "Node.java"]);
+    for (_, code) in &out {
+        assert!(!code.contains("package "));
+    }
+
+    let node = &out.iter().find(|(p, _)| p == "Node.java").unwrap().1;
+    assert!(node.contains("private List<Edge> edges;"));
+    assert!(node.contains("private Node parent;"));
+    let edge = &out.iter().find(|(p, _)| p == "Edge.java").unwrap().1;
+    assert!(edge.contains("private Node from;"));
+    assert!(edge.contains("private Node to;"));
+    assert!(edge.contains("private Kind kind;"));
+    assert!(edge.contains("    public enum Kind {\n"));
+
+    // a registry over the default package keeps simple names too
+    let registry = TypeRegistry::from_models([&model]);
+    let with_registry = generate_java_with_registry(&model, &JavaGenOptions::default(), &registry);
+    assert_eq!(with_registry, out);
+}
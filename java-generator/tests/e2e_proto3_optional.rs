@@ -0,0 +1,27 @@
+use java_generator::generate_java_from_proto;
+
+#[test]
+fn e2e_proto3_optional_scalars_are_boxed_with_presence() {
+    let files =
+        generate_java_from_proto("tests/resources/proto3_optional.proto").expect("generation");
+    let cart = &files
+        .iter()
+        .find(|(p, _)| p == "shop/Cart.java")
+        .expect("Cart generated")
+        .1;
+
+    // implicit presence: primitive, no has/clear
+    assert!(cart.contains("private int size;"));
+    assert!(!cart.contains("hasSize"));
+
+    // explicit `optional`: nullable boxed field with presence methods
+    assert!(cart.contains("private Integer count;"));
+    assert!(cart.contains("public Integer getCount() { return this.count; }"));
+    assert!(cart.contains("public void setCount(Integer value) { this.count = value; }"));
+    assert!(cart.contains("public boolean hasCount() { return this.count != null; }"));
+    assert!(cart.contains("public void clearCount() { this.count = null; }"));
+    assert!(cart.contains("private Boolean gift;"));
+    assert!(cart.contains("public boolean hasGift() { return this.gift != null; }"));
+    assert!(cart.contains("private String coupon;"));
+    assert!(cart.contains("public boolean hasCoupon() { return this.coupon != null; }"));
+}
@@ -0,0 +1,150 @@
+use parser::{Field, FieldLabel, FieldType, ProtoModel, ScalarType, TypeDecl};
+
+/// Document the canonical proto3 JSON mapping of every message as Markdown:
+/// one table per message listing each field's JSON key and how its value is
+/// encoded, for teams serializing by hand.
+pub fn generate_json_mapping(model: &ProtoModel) -> String {
+    let mut out = String::from("# Proto3 JSON mapping\n");
+    if let Some(pkg) = &model.package {
+        out.push_str(&format!("\nPackage `{}`.\n", pkg));
+    }
+    for t in &model.types {
+        let TypeDecl::Message(m) = t else { continue };
+        out.push_str(&format!("\n## {}\n\n", m.name));
+        if m.fields.is_empty() {
+            out.push_str("Encoded as `{}`.\n");
+            continue;
+        }
+        out.push_str("| Field | JSON key | Proto type | JSON encoding |\n");
+        out.push_str("|---|---|---|---|\n");
+        for f in &m.fields {
+            out.push_str(&format!(
+                "| {} | `{}` | {} | {} |\n",
+                f.name,
+                f.json_key(),
+                proto_type(f),
+                encoding(model, &m.name, f)
+            ));
+        }
+    }
+    out
+}
+
+fn proto_type(f: &Field) -> String {
+    let base = type_text(&f.ty);
+    match f.label {
+        Some(FieldLabel::Repeated) => format!("repeated {}", base),
+        _ => base,
+    }
+}
+
+fn type_text(ty: &FieldType) -> String {
+    match ty {
+        FieldType::Scalar(st) => format!("{:?}", st).to_lowercase(),
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => name.clone(),
+        FieldType::Map { key, value } => format!(
+            "map<{}, {}>",
+            format!("{:?}", key).to_lowercase(),
+            type_text(value)
+        ),
+    }
+}
+
+fn encoding(model: &ProtoModel, scope: &str, f: &Field) -> String {
+    let single = value_encoding(model, scope, &f.ty);
+    match f.label {
+        Some(FieldLabel::Repeated) => format!("array of {}", single),
+        _ => single,
+    }
+}
+
+fn value_encoding(model: &ProtoModel, scope: &str, ty: &FieldType) -> String {
+    match ty {
+        FieldType::Scalar(st) => scalar_encoding(st).to_string(),
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => {
+            custom_encoding(model, scope, name).to_string()
+        }
+        // keys are always JSON strings, whatever their proto type
+        FieldType::Map { value, .. } => format!(
+            "object with string keys; values: {}",
+            value_encoding(model, scope, value)
+        ),
+    }
+}
+
+fn scalar_encoding(st: &ScalarType) -> &'static str {
+    match st {
+        ScalarType::Int32
+        | ScalarType::Sint32
+        | ScalarType::Sfixed32
+        | ScalarType::Uint32
+        | ScalarType::Fixed32 => "number",
+        // 64-bit values exceed a JSON double's exact range
+        ScalarType::Int64
+        | ScalarType::Sint64
+        | ScalarType::Sfixed64
+        | ScalarType::Uint64
+        | ScalarType::Fixed64 => "decimal string, e.g. `\"1\"`",
+        ScalarType::Float | ScalarType::Double => {
+            "number, or `\"NaN\"`/`\"Infinity\"`/`\"-Infinity\"`"
+        }
+        ScalarType::Bool => "`true`/`false`",
+        ScalarType::String => "string",
+        ScalarType::Bytes => "base64 string",
+    }
+}
+
+fn custom_encoding(model: &ProtoModel, scope: &str, name: &str) -> &'static str {
+    match name.trim_start_matches('.') {
+        "google.protobuf.Timestamp" => {
+            return "RFC 3339 string, e.g. `\"1972-01-01T10:00:20.021Z\"`";
+        }
+        "google.protobuf.Duration" => return "seconds string with `s` suffix, e.g. `\"1.5s\"`",
+        _ => {}
+    }
+    let is_enum = model.resolve_type_name(scope, name).is_some_and(|n| {
+        model
+            .types
+            .iter()
+            .any(|t| matches!(t, TypeDecl::Enum(e) if e.name == n))
+    });
+    if is_enum {
+        "enum value name as a string"
+    } else {
+        "object"
+    }
+}
+
+// Test module.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_int64_and_bytes_encodings() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            message Blob {
+                int64 size_bytes = 1;
+                bytes payload = 2;
+                int32 version = 3 [json_name = "v"];
+                Kind kind = 4;
+                repeated Blob parts = 5;
+                enum Kind { RAW = 0; }
+            }
+        "#,
+        )
+        .unwrap();
+        let doc = generate_json_mapping(&model);
+        assert!(doc.contains("## Blob\n"));
+        assert!(
+            doc.contains("| size_bytes | `sizeBytes` | int64 | decimal string, e.g. `\"1\"` |\n")
+        );
+        assert!(doc.contains("| payload | `payload` | bytes | base64 string |\n"));
+        assert!(doc.contains("| version | `v` | int32 | number |\n"));
+        assert!(doc.contains("| kind | `kind` | Blob.Kind | enum value name as a string |\n"));
+        assert!(doc.contains("| parts | `parts` | repeated Blob | array of object |\n"));
+    }
+}
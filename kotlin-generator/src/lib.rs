@@ -0,0 +1,348 @@
+use std::path::Path;
+
+use parser::{
+    Enum, Field, FieldLabel, FieldType, Message, ParseError, ProtoModel, ScalarType, TypeDecl,
+    parse_proto_file,
+};
+
+/// A generated file: (relative_file_path, file_content).
+pub type GeneratedFile = (String, String);
+
+/// Options controlling the shape of the generated Kotlin code.
+#[derive(Debug, Clone, Default)]
+pub struct KotlinGenOptions {
+    /// Annotate classes, oneof hierarchies and enums with kotlinx.serialization's
+    /// `@Serializable`, and properties with a `json_name` override with
+    /// `@SerialName`.
+    pub serializable: bool,
+}
+
+/// Generate Kotlin sources from a .proto file path.
+pub fn generate_kotlin_from_proto<P: AsRef<Path>>(
+    proto_path: P,
+) -> Result<Vec<GeneratedFile>, ParseError> {
+    let model = parse_proto_file(proto_path)?;
+    Ok(generate_kotlin_from_model(&model))
+}
+
+/// Generate one `.kt` file per top-level message/enum. Nested types become
+/// nested classes, and each `oneof` becomes a nested `sealed class` with one
+/// subclass per member so consumers get an exhaustive `when`.
+pub fn generate_kotlin_from_model(model: &ProtoModel) -> Vec<GeneratedFile> {
+    generate_kotlin_with_options(model, &KotlinGenOptions::default())
+}
+
+/// Generate Kotlin sources from the model using the given options.
+pub fn generate_kotlin_with_options(
+    model: &ProtoModel,
+    opts: &KotlinGenOptions,
+) -> Vec<GeneratedFile> {
+    let pkg_dir = model
+        .package
+        .as_ref()
+        .map(|p| format!("{}/", p.replace('.', "/")))
+        .unwrap_or_default();
+
+    let mut out = Vec::new();
+    for t in &model.types {
+        let name = t.name();
+        if name.contains('.') {
+            continue; // rendered inside its parent
+        }
+        let mut code = String::new();
+        if let Some(pkg) = &model.package {
+            code.push_str(&format!("package {}\n\n", pkg));
+        }
+        if opts.serializable {
+            code.push_str("import kotlinx.serialization.*\n\n");
+        }
+        render_type(model, t, 0, opts, &mut code);
+        out.push((format!("{}{}.kt", pkg_dir, name), code));
+    }
+    out
+}
+
+fn simple_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+fn render_type(
+    model: &ProtoModel,
+    t: &TypeDecl,
+    depth: usize,
+    opts: &KotlinGenOptions,
+    out: &mut String,
+) {
+    if opts.serializable {
+        out.push_str(&format!("{}@Serializable\n", "    ".repeat(depth)));
+    }
+    match t {
+        TypeDecl::Message(m) => render_message(model, m, depth, opts, out),
+        TypeDecl::Enum(e) => render_enum(e, depth, out),
+    }
+}
+
+fn render_message(
+    model: &ProtoModel,
+    m: &Message,
+    depth: usize,
+    opts: &KotlinGenOptions,
+    out: &mut String,
+) {
+    let pad = "    ".repeat(depth);
+    let class = simple_name(&m.name);
+    let serializable = if opts.serializable {
+        "@Serializable "
+    } else {
+        ""
+    };
+
+    // constructor properties: plain fields, then one property per oneof
+    let mut props = Vec::new();
+    let mut oneofs: Vec<(&str, Vec<&Field>)> = Vec::new();
+    for f in &m.fields {
+        match f.oneof.as_deref() {
+            None => {
+                let serial_name = match &f.json_name {
+                    Some(json) if opts.serializable => format!("@SerialName(\"{}\") ", json),
+                    _ => String::new(),
+                };
+                props.push(format!(
+                    "{}val {}: {}",
+                    serial_name,
+                    f.name,
+                    property_type(model, m, f)
+                ))
+            }
+            Some(o) => match oneofs.iter_mut().find(|(name, _)| *name == o) {
+                Some((_, members)) => members.push(f),
+                None => {
+                    props.push(format!("val {}: {}? = null", o, upper_camel(o)));
+                    oneofs.push((o, vec![f]));
+                }
+            },
+        }
+    }
+
+    let nested: Vec<&TypeDecl> = model.nested_types(&m.name).collect();
+
+    // a data class needs at least one property
+    if props.is_empty() {
+        out.push_str(&format!("{}class {}", pad, class));
+    } else {
+        out.push_str(&format!("{}data class {}(\n", pad, class));
+        for p in &props {
+            out.push_str(&format!("{}    {},\n", pad, p));
+        }
+        out.push_str(&format!("{})", pad));
+    }
+    if oneofs.is_empty() && nested.is_empty() {
+        out.push('\n');
+        return;
+    }
+    out.push_str(" {\n");
+    for (name, members) in &oneofs {
+        let sealed = upper_camel(name);
+        out.push_str(&format!(
+            "{}    {}sealed class {} {{\n",
+            pad, serializable, sealed
+        ));
+        for f in members {
+            out.push_str(&format!(
+                "{}        {}data class {}(val value: {}) : {}()\n",
+                pad,
+                serializable,
+                upper_camel(&f.name),
+                kotlin_type(model, m, &f.ty),
+                sealed
+            ));
+        }
+        out.push_str(&format!("{}    }}\n", pad));
+    }
+    for t in nested {
+        render_type(model, t, depth + 1, opts, out);
+    }
+    out.push_str(&format!("{}}}\n", pad));
+}
+
+fn render_enum(e: &Enum, depth: usize, out: &mut String) {
+    let pad = "    ".repeat(depth);
+    out.push_str(&format!(
+        "{}enum class {}(val number: Int) {{\n",
+        pad,
+        simple_name(&e.name)
+    ));
+    for (i, v) in e.values.iter().enumerate() {
+        let sep = if i + 1 == e.values.len() { ";" } else { "," };
+        out.push_str(&format!("{}    {}({}){}\n", pad, v.name, v.number, sep));
+    }
+    out.push_str(&format!("{}}}\n", pad));
+}
+
+// Property type with its default: proto3 scalars default to their zero
+// value, message/enum and explicit-presence fields are nullable.
+fn property_type(model: &ProtoModel, m: &Message, f: &Field) -> String {
+    let kt = kotlin_type(model, m, &f.ty);
+    match (f.label, &f.ty) {
+        (Some(FieldLabel::Repeated), _) => format!("List<{}> = emptyList()", kt),
+        (_, FieldType::Map { .. }) => format!("{} = emptyMap()", kt),
+        (Some(FieldLabel::Optional), _)
+        | (_, FieldType::Custom(_) | FieldType::Message(_) | FieldType::Enum(_)) => {
+            format!("{}? = null", kt)
+        }
+        (_, FieldType::Scalar(st)) => format!("{} = {}", kt, scalar_default(st)),
+    }
+}
+
+fn kotlin_type(model: &ProtoModel, m: &Message, ft: &FieldType) -> String {
+    match ft {
+        FieldType::Scalar(st) => match st {
+            ScalarType::Double => "Double".into(),
+            ScalarType::Float => "Float".into(),
+            ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => "Int".into(),
+            ScalarType::Uint32 | ScalarType::Fixed32 => "Int".into(),
+            ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => "Long".into(),
+            ScalarType::Uint64 | ScalarType::Fixed64 => "Long".into(),
+            ScalarType::Bool => "Boolean".into(),
+            ScalarType::String => "String".into(),
+            ScalarType::Bytes => "ByteArray".into(),
+        },
+        // The package-relative declared name (`Order.Address`) is valid from
+        // anywhere in the package; unknown (imported) names are kept as written.
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => model
+            .resolve_type_name(&m.name, name)
+            .unwrap_or_else(|| name.clone()),
+        FieldType::Map { key, value } => format!(
+            "Map<{}, {}>",
+            kotlin_type(model, m, &FieldType::Scalar(key.clone())),
+            kotlin_type(model, m, value)
+        ),
+    }
+}
+
+fn scalar_default(st: &ScalarType) -> &'static str {
+    match st {
+        ScalarType::Double => "0.0",
+        ScalarType::Float => "0.0f",
+        ScalarType::Int64
+        | ScalarType::Uint64
+        | ScalarType::Sint64
+        | ScalarType::Fixed64
+        | ScalarType::Sfixed64 => "0L",
+        ScalarType::Bool => "false",
+        ScalarType::String => "\"\"",
+        ScalarType::Bytes => "ByteArray(0)",
+        _ => "0",
+    }
+}
+
+// `price_cents` -> `PriceCents`
+fn upper_camel(s: &str) -> String {
+    s.split('_')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let mut it = p.chars();
+            match it.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + it.as_str(),
+            }
+        })
+        .collect()
+}
+
+// Test module.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oneof_becomes_sealed_class() {
+        let files = generate_kotlin_from_proto("../parser/tests/resources/order.proto")
+            .expect("generation failed");
+        let (_, item) = files
+            .iter()
+            .find(|(p, _)| p == "me/alekseinovikov/proto/OrderItem.kt")
+            .expect("OrderItem generated");
+        assert!(item.starts_with("package me.alekseinovikov.proto\n\n"));
+        assert!(item.contains("data class OrderItem(\n"));
+        assert!(item.contains("    val name: String = \"\",\n"));
+        assert!(item.contains("    val count: Long? = null,\n"));
+        assert!(item.contains("    val pricing: Pricing? = null,\n"));
+        assert!(item.contains("    sealed class Pricing {\n"));
+        assert!(item.contains("        data class PriceDecimal(val value: Double) : Pricing()\n"));
+        assert!(item.contains("        data class PriceCents(val value: Long) : Pricing()\n"));
+        // members live only in the sealed hierarchy, not as flat properties
+        assert!(!item.contains("val price_cents"));
+    }
+
+    #[test]
+    fn nested_types_render_inside_parent() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+                Address shipping = 1;
+                repeated Status history = 2;
+                message Address { string city = 1; }
+                enum Status { NEW = 0; DONE = 1; }
+            }
+            message Empty {}
+        "#,
+        )
+        .unwrap();
+        let files = generate_kotlin_from_model(&model);
+        let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["Order.kt", "Empty.kt"]);
+        let order = &files[0].1;
+        assert!(order.contains("    val shipping: Order.Address? = null,\n"));
+        assert!(order.contains("    val history: List<Order.Status> = emptyList(),\n"));
+        assert!(
+            order.contains("    data class Address(\n        val city: String = \"\",\n    )\n")
+        );
+        assert!(order.contains(
+            "    enum class Status(val number: Int) {\n        NEW(0),\n        DONE(1);\n    }\n"
+        ));
+        assert_eq!(files[1].1, "class Empty\n");
+    }
+
+    #[test]
+    fn serializable_annotations() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+                string display_name = 1 [json_name = "name"];
+                Status status = 2;
+                oneof payment { string card = 3; int64 cents = 4; }
+                enum Status { NEW = 0; }
+            }
+            enum Kind { KIND_UNSPECIFIED = 0; }
+        "#,
+        )
+        .unwrap();
+        let opts = KotlinGenOptions { serializable: true };
+        let files = generate_kotlin_with_options(&model, &opts);
+        let order = &files[0].1;
+        assert!(order.starts_with(
+            "package shop\n\nimport kotlinx.serialization.*\n\n@Serializable\ndata class Order(\n"
+        ));
+        assert!(order.contains("    @SerialName(\"name\") val display_name: String = \"\",\n"));
+        assert!(order.contains("    val status: Order.Status? = null,\n"));
+        assert!(order.contains("    @Serializable sealed class Payment {\n"));
+        assert!(
+            order
+                .contains("        @Serializable data class Card(val value: String) : Payment()\n")
+        );
+        assert!(order.contains("    @Serializable\n    enum class Status(val number: Int) {\n"));
+        assert!(
+            files[1]
+                .1
+                .contains("@Serializable\nenum class Kind(val number: Int) {\n")
+        );
+
+        let plain = &generate_kotlin_from_model(&model)[0].1;
+        assert!(!plain.contains("Serializ"));
+        assert!(!plain.contains("@SerialName"));
+    }
+}
@@ -0,0 +1,265 @@
+use parser::{Field, FieldLabel, FieldType, ProtoModel, ScalarType, TypeDecl};
+
+/// Render every message and enum as an OpenAPI 3.0 `components.schemas`
+/// entry, following the proto3 JSON mapping: properties are keyed by JSON
+/// name, 64-bit integers are strings, enums are arrays of value names and
+/// message references are `$ref`s. Returns a JSON document fragment
+/// (`{"components": {"schemas": {...}}}`) to merge into an API spec.
+pub fn generate_openapi_schemas(model: &ProtoModel) -> String {
+    let mut schemas = Vec::new();
+    for t in &model.types {
+        let schema = match t {
+            TypeDecl::Message(m) if m.is_map_entry() => continue,
+            TypeDecl::Message(m) => {
+                let properties = m
+                    .fields
+                    .iter()
+                    .map(|f| (f.json_key(), field_schema(model, &m.name, f)))
+                    .collect();
+                let mut schema = vec![
+                    ("type".to_string(), Json::str("object")),
+                    ("properties".to_string(), Json::Object(properties)),
+                ];
+                let required: Vec<Json> = m
+                    .fields
+                    .iter()
+                    .filter(|f| f.label == Some(FieldLabel::Required))
+                    .map(|f| Json::Str(f.json_key()))
+                    .collect();
+                if !required.is_empty() {
+                    schema.push(("required".to_string(), Json::Array(required)));
+                }
+                Json::Object(schema)
+            }
+            TypeDecl::Enum(e) => Json::Object(vec![
+                ("type".to_string(), Json::str("string")),
+                (
+                    "enum".to_string(),
+                    Json::Array(e.values.iter().map(|v| Json::Str(v.name.clone())).collect()),
+                ),
+            ]),
+        };
+        schemas.push((t.name().to_string(), schema));
+    }
+    let doc = Json::Object(vec![(
+        "components".to_string(),
+        Json::Object(vec![("schemas".to_string(), Json::Object(schemas))]),
+    )]);
+    let mut out = String::new();
+    doc.write(0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn field_schema(model: &ProtoModel, scope: &str, f: &Field) -> Json {
+    let single = type_schema(model, scope, &f.ty);
+    match f.label {
+        Some(FieldLabel::Repeated) => Json::Object(vec![
+            ("type".to_string(), Json::str("array")),
+            ("items".to_string(), single),
+        ]),
+        // explicit presence; in 3.0 a `$ref` ignores siblings, so wrap it
+        Some(FieldLabel::Optional) => match single {
+            Json::Object(schema) if schema[0].0 == "$ref" => Json::Object(vec![
+                ("allOf".to_string(), Json::Array(vec![Json::Object(schema)])),
+                ("nullable".to_string(), Json::Bool(true)),
+            ]),
+            Json::Object(mut schema) => {
+                schema.push(("nullable".to_string(), Json::Bool(true)));
+                Json::Object(schema)
+            }
+            other => other,
+        },
+        _ => single,
+    }
+}
+
+fn type_schema(model: &ProtoModel, scope: &str, ty: &FieldType) -> Json {
+    match ty {
+        FieldType::Scalar(st) => scalar_schema(st),
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => {
+            custom_schema(model, scope, name)
+        }
+        // map keys are always JSON object keys, i.e. strings
+        FieldType::Map { value, .. } => Json::Object(vec![
+            ("type".to_string(), Json::str("object")),
+            (
+                "additionalProperties".to_string(),
+                type_schema(model, scope, value),
+            ),
+        ]),
+    }
+}
+
+fn scalar_schema(st: &ScalarType) -> Json {
+    let (ty, format) = match st {
+        ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => ("integer", "int32"),
+        ScalarType::Uint32 | ScalarType::Fixed32 => ("integer", "int64"),
+        // 64-bit values exceed a JSON double's exact range
+        ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => ("string", "int64"),
+        ScalarType::Uint64 | ScalarType::Fixed64 => ("string", "uint64"),
+        ScalarType::Float => ("number", "float"),
+        ScalarType::Double => ("number", "double"),
+        ScalarType::Bool => ("boolean", ""),
+        ScalarType::String => ("string", ""),
+        ScalarType::Bytes => ("string", "byte"),
+    };
+    typed(ty, format)
+}
+
+fn custom_schema(model: &ProtoModel, scope: &str, name: &str) -> Json {
+    let name = name.trim_start_matches('.');
+    match name {
+        "google.protobuf.Timestamp" => return typed("string", "date-time"),
+        "google.protobuf.Duration" => return typed("string", ""),
+        _ => {}
+    }
+    // local types by declared name, others as written
+    let target = model
+        .resolve_type_name(scope, name)
+        .unwrap_or_else(|| name.to_string());
+    Json::Object(vec![(
+        "$ref".to_string(),
+        Json::Str(format!("#/components/schemas/{}", target)),
+    )])
+}
+
+fn typed(ty: &str, format: &str) -> Json {
+    let mut schema = vec![("type".to_string(), Json::str(ty))];
+    if !format.is_empty() {
+        schema.push(("format".to_string(), Json::str(format)));
+    }
+    Json::Object(schema)
+}
+
+// Just enough JSON to write the document, with keys kept in insertion order.
+enum Json {
+    Str(String),
+    Bool(bool),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn str(s: &str) -> Json {
+        Json::Str(s.to_string())
+    }
+
+    fn write(&self, depth: usize, out: &mut String) {
+        let pad = "  ".repeat(depth + 1);
+        match self {
+            Json::Str(s) => write_string(s, out),
+            Json::Bool(b) => out.push_str(&b.to_string()),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Object(entries) if entries.is_empty() => out.push_str("{}"),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    item.write(depth + 1, out);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&format!("{}]", "  ".repeat(depth)));
+            }
+            Json::Object(entries) => {
+                out.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(&pad);
+                    write_string(key, out);
+                    out.push_str(": ");
+                    value.write(depth + 1, out);
+                    out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&format!("{}}}", "  ".repeat(depth)));
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// Test module.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_per_message_with_property_types() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+                int64 id = 1;
+                string display_name = 2;
+                optional double discount = 3;
+                repeated Line lines = 4;
+                Status status = 5;
+                optional Address shipping = 6;
+                bytes signature = 7 [json_name = "sig"];
+                map<string, Line> by_sku = 8;
+                enum Status { NEW = 0; PAID = 1; }
+            }
+            message Line { uint32 qty = 1; }
+            message Address { string city = 1; }
+        "#,
+        )
+        .unwrap();
+        let doc = generate_openapi_schemas(&model);
+        assert!(doc.starts_with("{\n  \"components\": {\n    \"schemas\": {\n"));
+        for name in ["Order.Status", "Order", "Line", "Address"] {
+            assert!(
+                doc.contains(&format!("      \"{}\": {{\n", name)),
+                "{}",
+                name
+            );
+        }
+        let prop = |key: &str, body: &str| {
+            format!(
+                "          \"{}\": {{\n            {}\n          }}",
+                key, body
+            )
+        };
+        assert!(doc.contains(&prop(
+            "id",
+            "\"type\": \"string\",\n            \"format\": \"int64\""
+        )));
+        assert!(doc.contains(&prop("displayName", "\"type\": \"string\"")));
+        assert!(doc.contains(&prop(
+            "discount",
+            "\"type\": \"number\",\n            \"format\": \"double\",\n            \"nullable\": true"
+        )));
+        assert!(doc.contains(&prop(
+            "lines",
+            "\"type\": \"array\",\n            \"items\": {\n              \"$ref\": \"#/components/schemas/Line\"\n            }"
+        )));
+        assert!(doc.contains(&prop(
+            "status",
+            "\"$ref\": \"#/components/schemas/Order.Status\""
+        )));
+        assert!(doc.contains("          \"shipping\": {\n            \"allOf\": [\n              {\n                \"$ref\": \"#/components/schemas/Address\"\n              }\n            ],\n            \"nullable\": true\n"));
+        assert!(doc.contains(&prop(
+            "bySku",
+            "\"type\": \"object\",\n            \"additionalProperties\": {\n              \"$ref\": \"#/components/schemas/Line\"\n            }"
+        )));
+        assert!(doc.contains(&prop(
+            "sig",
+            "\"type\": \"string\",\n            \"format\": \"byte\""
+        )));
+        assert!(doc.contains(
+            "      \"Order.Status\": {\n        \"type\": \"string\",\n        \"enum\": [\n          \"NEW\",\n          \"PAID\"\n        ]\n      }"
+        ));
+    }
+}
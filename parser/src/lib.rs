@@ -1,13 +1,25 @@
+mod loader;
 mod model;
+mod passes;
+mod printer;
+mod validate;
+mod warnings;
 
 use pest::Parser as _;
 use pest::iterators::Pair;
 use pest_derive::Parser;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 
+pub use loader::*;
 pub use model::*;
+pub use passes::*;
+pub use printer::*;
+pub use validate::*;
+pub use warnings::*;
 
 #[derive(Parser)]
 #[grammar = "resources/proto.pest"] // Path relative to the crate root
@@ -18,6 +30,33 @@ pub enum ParseError {
     Io(std::io::Error),
     Pest(Box<pest::error::Error<Rule>>),
     Message(&'static str),
+    InvalidNumber(String),
+    // strict mode: constructs the IR does not model, one entry each
+    Unsupported(Vec<String>),
+    // message nested deeper than ParseOptions::max_nesting_depth
+    NestingTooDeep {
+        message: String,
+        limit: usize,
+    },
+    // any of the above, raised while parsing `path`
+    InFile {
+        path: PathBuf,
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Attach the file being parsed. An error that already names a file
+    /// keeps it, so the innermost (most precise) path wins.
+    pub fn with_file<P: AsRef<Path>>(self, path: P) -> Self {
+        match self {
+            ParseError::InFile { .. } => self,
+            error => ParseError::InFile {
+                path: path.as_ref().to_path_buf(),
+                error: Box::new(error),
+            },
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -26,6 +65,16 @@ impl fmt::Display for ParseError {
             ParseError::Io(e) => write!(f, "IO error: {}", e),
             ParseError::Pest(e) => write!(f, "Parse error: {}", e),
             ParseError::Message(m) => write!(f, "{}", m),
+            ParseError::InvalidNumber(m) => write!(f, "Invalid number: {}", m),
+            ParseError::Unsupported(items) => {
+                write!(f, "Unsupported constructs: {}", items.join("; "))
+            }
+            ParseError::NestingTooDeep { message, limit } => write!(
+                f,
+                "{}: messages are nested more than {} levels deep",
+                message, limit
+            ),
+            ParseError::InFile { path, error } => write!(f, "{}: {}", path.display(), error),
         }
     }
 }
@@ -45,56 +94,138 @@ impl From<pest::error::Error<Rule>> for ParseError {
 
 // Public API: parse a .proto file into ProtoModel IR
 pub fn parse_proto_file<P: AsRef<Path>>(path: P) -> Result<ProtoModel, ParseError> {
-    let content = fs::read_to_string(path)?;
-    let mut pairs = ProtoParser::parse(Rule::proto, &content)?;
+    let path = path.as_ref();
+    fs::read_to_string(path)
+        .map_err(ParseError::Io)
+        .and_then(|content| parse_proto_str(&content))
+        .map_err(|e| e.with_file(path))
+}
+
+// Public API: parse .proto source text into ProtoModel IR
+pub fn parse_proto_str(content: &str) -> Result<ProtoModel, ParseError> {
+    parse_proto_str_with_options(content, &ParseOptions::default())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Fail with `ParseError::Unsupported` instead of silently dropping
+    /// constructs the IR does not model (file options, message `reserved`, ...).
+    pub strict: bool,
+    /// Fail with `ParseError::NestingTooDeep` when messages (or groups) nest
+    /// deeper than this; a top-level message is depth 1.
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            max_nesting_depth: 100,
+        }
+    }
+}
+
+pub fn parse_proto_str_with_options(
+    content: &str,
+    options: &ParseOptions,
+) -> Result<ProtoModel, ParseError> {
+    // Editors on Windows may prepend a UTF-8 BOM; CRLF is plain whitespace to the grammar
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut pairs = ProtoParser::parse(Rule::proto, content)?;
     let proto_pair = pairs
         .next()
         .ok_or(ParseError::Message("expected proto root"))?;
-    Ok(parse_proto(proto_pair))
+    if options.strict {
+        let mut dropped = Vec::new();
+        collect_unmodeled(proto_pair.clone(), None, &mut dropped);
+        if !dropped.is_empty() {
+            return Err(ParseError::Unsupported(dropped));
+        }
+    }
+    parse_proto(proto_pair, options)
+}
+
+/// Serialize a model to JSON, e.g. to cache it on disk between builds.
+#[cfg(feature = "serde")]
+pub fn model_to_json(model: &ProtoModel) -> Result<String, serde_json::Error> {
+    serde_json::to_string(model)
+}
+
+/// Reload a model written by `model_to_json` without re-parsing proto text;
+/// the result equals the model that was serialized.
+#[cfg(feature = "serde")]
+pub fn model_from_json(json: &str) -> Result<ProtoModel, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+// Like parse_proto_str, also reporting non-fatal issues such as a misspelled
+// scalar type that fell through to a custom type reference.
+pub fn parse_proto_str_with_warnings(content: &str) -> Result<ParseResult, ParseError> {
+    let model = parse_proto_str(content)?;
+    let warnings = collect_warnings(&model);
+    Ok(ParseResult { model, warnings })
 }
 
-fn parse_proto(pair: Pair<Rule>) -> ProtoModel {
-    let mut model = ProtoModel::default();
+fn parse_proto(pair: Pair<Rule>, options: &ParseOptions) -> Result<ProtoModel, ParseError> {
+    // Like protoc, a file without a syntax statement is proto2
+    let mut model = ProtoModel {
+        syntax: ProtoSyntax::Proto2,
+        file_comment: file_comment(pair.get_input()),
+        ..Default::default()
+    };
     let mut types: Vec<TypeDecl> = Vec::new();
+    let mut services: Vec<Service> = Vec::new();
+    let mut extensions: Vec<Extension> = Vec::new();
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
-            Rule::package_statement => {
-                // package_statement = { "package" ~ package_name ~ ";" }
-                let pkg = inner
+            Rule::syntax_statement => {
+                // syntax_statement = { "syntax" ~ "=" ~ syntax_name ~ ";" }
+                let name = inner.as_str();
+                if name.contains("\"proto3\"") || name.contains("'proto3'") {
+                    model.syntax = ProtoSyntax::Proto3;
+                }
+            }
+            Rule::edition_statement => {
+                // edition_statement = { "edition" ~ "=" ~ edition_name ~ ";" }
+                model.syntax = ProtoSyntax::Proto3;
+                model.edition = inner
                     .into_inner()
-                    .find(|p| p.as_rule() == Rule::package_name)
-                    .and_then(|p| p.into_inner().find(|x| x.as_rule() == Rule::full_ident))
-                    .map(|p| p.as_str().to_string());
-                model.package = pkg;
+                    .find(|p| p.as_rule() == Rule::edition_name)
+                    .map(|p| unescape_string(p.as_str().trim()));
             }
+            Rule::package_statement => model.package = parse_package(inner),
             Rule::proto_body => {
                 // Unwrap proto_body -> may contain top_level_definition etc.
                 for b in inner.into_inner() {
                     match b.as_rule() {
-                        Rule::package_statement => {
-                            let pkg = b
-                                .into_inner()
-                                .find(|p| p.as_rule() == Rule::package_name)
-                                .and_then(|p| {
-                                    p.into_inner().find(|x| x.as_rule() == Rule::full_ident)
-                                })
-                                .map(|p| p.as_str().to_string());
-                            model.package = pkg;
+                        Rule::package_statement => model.package = parse_package(b),
+                        Rule::import_statement => model.imports.push(parse_import(b)),
+                        Rule::option_entry => {
+                            // only `features` are kept from file options
+                            for o in b.into_inner() {
+                                if let Some((name, value)) = parse_option(o)?
+                                    && let Some(feature) = name.strip_prefix("features.")
+                                {
+                                    model.features.insert(feature.to_string(), value);
+                                }
+                            }
                         }
                         Rule::top_level_definition => {
                             for def in b.into_inner() {
                                 match def.as_rule() {
                                     Rule::message_block => {
-                                        parse_message_block(def, None, &mut types)
+                                        parse_message_block(def, None, &mut types, options)?
                                     }
-                                    Rule::enum_block => parse_enum_block(def, None, &mut types),
+                                    Rule::enum_block => parse_enum_block(def, None, &mut types)?,
+                                    Rule::service_block => services.push(parse_service_block(def)?),
+                                    Rule::extend_block => extensions.push(parse_extend_block(def)?),
                                     _ => {}
                                 }
                             }
                         }
-                        Rule::message_block => parse_message_block(b, None, &mut types),
-                        Rule::enum_block => parse_enum_block(b, None, &mut types),
+                        Rule::message_block => parse_message_block(b, None, &mut types, options)?,
+                        Rule::enum_block => parse_enum_block(b, None, &mut types)?,
                         _ => {}
                     }
                 }
@@ -104,7 +235,59 @@ fn parse_proto(pair: Pair<Rule>) -> ProtoModel {
     }
 
     model.types = types;
-    model
+    model.services = services;
+    model.extensions = extensions;
+    Ok(model)
+}
+
+// Constructs the grammar accepts but the IR drops, by the rule containing them
+fn collect_unmodeled(pair: Pair<Rule>, parent: Option<Rule>, found: &mut Vec<String>) {
+    let rule = pair.as_rule();
+    let kind = match (parent, rule) {
+        (Some(Rule::proto_body), Rule::option_entry) if !is_features_option(&pair) => "file option",
+        (Some(Rule::message_element), Rule::reserved) => "message reserved statement",
+        (Some(Rule::message_element), Rule::extend_block) => "nested extend",
+        (Some(Rule::extend_block_entry), Rule::group_block) => "group extension",
+        (Some(Rule::enum_body), Rule::option_entry) => "enum option",
+        (Some(Rule::oneof_field), Rule::option_entry) => "oneof option",
+        (Some(Rule::service_element), Rule::option_entry) => "service option",
+        _ => {
+            for inner in pair.into_inner() {
+                collect_unmodeled(inner, Some(rule), found);
+            }
+            return;
+        }
+    };
+    let (line, _) = pair.line_col();
+    let text = pair.as_str().lines().next().unwrap_or("").trim();
+    found.push(format!("line {}: {} `{}`", line, kind, text));
+}
+
+fn parse_package(pair: Pair<Rule>) -> Option<String> {
+    // package_statement = { "package" ~ package_name ~ ";" }
+    // package_name = { ident ~ ("." ~ ident)* }, rejoined without whitespace
+    let name = pair
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::package_name)?;
+    let segments: Vec<&str> = name.into_inner().map(|p| p.as_str()).collect();
+    Some(segments.join("."))
+}
+
+fn parse_import(pair: Pair<Rule>) -> Import {
+    // import_statement = { "import" ~ import_kind? ~ file_reference ~ ";" }
+    let mut import = Import {
+        path: String::new(),
+        kind: ImportKind::Normal,
+    };
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::import_kind if p.as_str() == "public" => import.kind = ImportKind::Public,
+            Rule::import_kind => import.kind = ImportKind::Weak,
+            Rule::file_reference => import.path = unescape_string(p.as_str().trim()),
+            _ => {}
+        }
+    }
+    import
 }
 
 fn qualify(parent: Option<&str>, name: &str) -> String {
@@ -115,12 +298,14 @@ fn qualify(parent: Option<&str>, name: &str) -> String {
     }
 }
 
-fn parse_message_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<TypeDecl>) {
-    use std::collections::HashSet;
+fn parse_message_block(
+    block: Pair<Rule>,
+    parent: Option<&str>,
+    types: &mut Vec<TypeDecl>,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
     // message_block = { "message" ~ message_name ~ message_body }
     let mut name: Option<String> = None;
-    let mut message = Message::default();
-
     let mut body_opt: Option<Pair<Rule>> = None;
 
     for p in block.into_inner() {
@@ -136,72 +321,96 @@ fn parse_message_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<
     }
 
     let raw_name = name.unwrap_or_default();
-    message.name = qualify(parent, &raw_name);
+    parse_message_body(qualify(parent, &raw_name), body_opt, types, options)
+}
 
-    // Track nested type names to qualify field references when needed
-    let mut nested_names: HashSet<String> = HashSet::new();
+fn parse_message_body(
+    name: String,
+    body_opt: Option<Pair<Rule>>,
+    types: &mut Vec<TypeDecl>,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
+    use std::collections::HashSet;
 
-    if let Some(body) = body_opt.clone() {
-        for elem in body.clone().into_inner() {
-            if elem.as_rule() == Rule::message_element {
-                for inner in elem.into_inner() {
-                    match inner.as_rule() {
-                        Rule::enum_block => {
-                            // Extract enum name
-                            for p in inner.clone().into_inner() {
-                                if p.as_rule() == Rule::enum_name {
-                                    nested_names.insert(p.as_str().to_string());
-                                }
-                            }
-                        }
-                        Rule::message_block => {
-                            for p in inner.clone().into_inner() {
-                                if p.as_rule() == Rule::message_name {
-                                    nested_names.insert(p.as_str().to_string());
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
+    if name.split('.').count() > options.max_nesting_depth {
+        return Err(ParseError::NestingTooDeep {
+            message: name,
+            limit: options.max_nesting_depth,
+        });
     }
+    let mut message = Message {
+        name,
+        ..Message::default()
+    };
+
+    // Simple names of nested types, to qualify field references to them
+    let mut nested_names: HashSet<String> = HashSet::new();
 
     if let Some(body) = body_opt {
         for elem in body.into_inner() {
             if elem.as_rule() == Rule::message_element {
                 for inner in elem.into_inner() {
                     match inner.as_rule() {
-                        Rule::field => {
-                            if let Some(field) =
-                                parse_field(inner, Some(&message.name), Some(&nested_names))
-                            {
+                        Rule::field | Rule::map => {
+                            if let Some(field) = parse_field(inner)? {
                                 message.fields.push(field);
                             }
                         }
                         Rule::oneof => {
-                            // Flatten oneof fields into message fields (no grouping in IR)
+                            // Flatten oneof fields into message fields, tagging each
+                            // with the oneof it belongs to
+                            let mut oneof_name: Option<String> = None;
                             for oneof_inner in inner.into_inner() {
+                                if oneof_inner.as_rule() == Rule::oneof_name {
+                                    oneof_name = Some(oneof_inner.as_str().to_string());
+                                }
                                 if oneof_inner.as_rule() == Rule::oneof_field {
                                     for f in oneof_inner.into_inner() {
-                                        if f.as_rule() == Rule::field
-                                            && let Some(field) = parse_field(
-                                                f,
-                                                Some(&message.name),
-                                                Some(&nested_names),
-                                            )
-                                        {
+                                        let field = match f.as_rule() {
+                                            Rule::field => parse_field(f)?,
+                                            Rule::group_block => {
+                                                let field = parse_group_block(
+                                                    f,
+                                                    &message.name,
+                                                    types,
+                                                    options,
+                                                )?;
+                                                nested_names.insert(last_declared_name(types));
+                                                Some(field)
+                                            }
+                                            _ => None,
+                                        };
+                                        if let Some(mut field) = field {
+                                            field.oneof = oneof_name.clone();
                                             message.fields.push(field);
                                         }
                                     }
                                 }
                             }
                         }
-                        Rule::enum_block => parse_enum_block(inner, Some(&message.name), types),
+                        Rule::group_block => {
+                            let field = parse_group_block(inner, &message.name, types, options)?;
+                            nested_names.insert(last_declared_name(types));
+                            message.fields.push(field);
+                        }
+                        Rule::enum_block => {
+                            parse_enum_block(inner, Some(&message.name), types)?;
+                            nested_names.insert(last_declared_name(types));
+                        }
                         Rule::message_block => {
-                            parse_message_block(inner, Some(&message.name), types)
+                            parse_message_block(inner, Some(&message.name), types, options)?;
+                            nested_names.insert(last_declared_name(types));
                         }
+                        Rule::option_entry => {
+                            // option_entry = { "option" ~ option ~ ";" }
+                            for o in inner.into_inner() {
+                                if let Some(opt) = parse_option(o)? {
+                                    message.options.push(opt);
+                                }
+                            }
+                            message.features = features(&message.options);
+                        }
+                        Rule::extensions => parse_extension_ranges(inner, &mut message)?,
                         _ => {}
                     }
                 }
@@ -209,10 +418,70 @@ fn parse_message_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<
         }
     }
 
+    // A field may reference a nested type declared below it, so qualify
+    // references once the whole body has been seen
+    for f in &mut message.fields {
+        if let FieldType::Custom(tn) = f.ty.value_type_mut()
+            && !tn.contains('.')
+            && nested_names.contains(tn.as_str())
+        {
+            *tn = qualify(Some(&message.name), tn);
+        }
+    }
+
     types.push(TypeDecl::Message(message));
+    Ok(())
+}
+
+// group_block = { field_modifier? ~ "group" ~ group_name ~ "=" ~ tag ~ message_body }
+// A group declares a nested message plus a field of that type, named after
+// the group in lower case.
+fn parse_group_block(
+    block: Pair<Rule>,
+    parent: &str,
+    types: &mut Vec<TypeDecl>,
+    options: &ParseOptions,
+) -> Result<Field, ParseError> {
+    let trailing_doc = trailing_comment(&block);
+    let mut label = None;
+    let mut name = String::new();
+    let mut order = 0;
+    let mut body = None;
+    for p in block.into_inner() {
+        match p.as_rule() {
+            Rule::field_modifier => label = parse_field_label(p.as_str()),
+            Rule::group_name => name = p.as_str().to_string(),
+            Rule::tag => order = parse_tag(p.as_str())?,
+            Rule::message_body => body = Some(p),
+            _ => {}
+        }
+    }
+    let type_name = qualify(Some(parent), &name);
+    parse_message_body(type_name.clone(), body, types, options)?;
+    Ok(Field {
+        ty: FieldType::Custom(type_name),
+        name: name.to_lowercase(),
+        order,
+        label,
+        trailing_doc,
+        options: Vec::new(),
+        json_name: None,
+        oneof: None,
+        features: HashMap::new(),
+    })
+}
+
+// Simple name of the type just parsed; a message is pushed after its nested types
+fn last_declared_name(types: &[TypeDecl]) -> String {
+    let name = types.last().map(TypeDecl::name).unwrap_or_default();
+    name.rsplit('.').next().unwrap_or(name).to_string()
 }
 
-fn parse_enum_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<TypeDecl>) {
+fn parse_enum_block(
+    block: Pair<Rule>,
+    parent: Option<&str>,
+    types: &mut Vec<TypeDecl>,
+) -> Result<(), ParseError> {
     // enum_block = { "enum" ~ enum_name ~ "{" ~ enum_body* ~ "}" ~ ";"? }
     let mut name: Option<String> = None;
     let mut en = Enum::default();
@@ -225,16 +494,27 @@ fn parse_enum_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<Typ
             Rule::enum_body => {
                 for eb in p.into_inner() {
                     if eb.as_rule() == Rule::enum_field {
+                        let trailing_doc = trailing_comment(&eb);
+                        let leading_doc = leading_comment(&eb);
                         let mut val_name: Option<String> = None;
                         let mut number: Option<i32> = None;
+                        let mut options = Vec::new();
                         for ef in eb.into_inner() {
                             match ef.as_rule() {
                                 Rule::enum_field_name => val_name = Some(ef.as_str().to_string()),
                                 Rule::enum_field_value => {
-                                    // enum_field_value = integer_value
-                                    let n = parse_integer_value(ef);
-                                    number = Some(n as i64 as i32);
+                                    // enum_field_value = integer_value; enum numbers are int32
+                                    let s = ef.as_str();
+                                    let n = parse_int(s).map_err(|e| invalid_number(s, e))?;
+                                    let n = i32::try_from(n).map_err(|_| {
+                                        ParseError::InvalidNumber(format!(
+                                            "enum value `{}` is out of int32 range",
+                                            s
+                                        ))
+                                    })?;
+                                    number = Some(n);
                                 }
+                                Rule::field_options => options = parse_field_options(ef)?,
                                 _ => {}
                             }
                         }
@@ -242,8 +522,13 @@ fn parse_enum_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<Typ
                             en.values.push(EnumValue {
                                 name: vn,
                                 number: num,
+                                trailing_doc,
+                                leading_doc,
+                                options,
                             });
                         }
+                    } else if eb.as_rule() == Rule::reserved {
+                        parse_enum_reserved(eb, &mut en)?;
                     }
                 }
             }
@@ -254,49 +539,270 @@ fn parse_enum_block(block: Pair<Rule>, parent: Option<&str>, types: &mut Vec<Typ
     let raw_name = name.unwrap_or_default();
     en.name = qualify(parent, &raw_name);
     types.push(TypeDecl::Enum(en));
+    Ok(())
+}
+
+fn parse_extend_block(block: Pair<Rule>) -> Result<Extension, ParseError> {
+    // extend_block = { "extend" ~ type_reference ~ "{" ~ extend_block_entry* ~ "}" ~ ";"? }
+    let mut ext = Extension::default();
+    for p in block.into_inner() {
+        match p.as_rule() {
+            Rule::type_reference => ext.extendee = p.as_str().trim_start_matches('.').to_string(),
+            Rule::extend_block_entry => {
+                for entry in p.into_inner() {
+                    if entry.as_rule() == Rule::field
+                        && let Some(field) = parse_field(entry)?
+                    {
+                        ext.fields.push(field);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(ext)
+}
+
+fn parse_service_block(block: Pair<Rule>) -> Result<Service, ParseError> {
+    // service_block = { "service" ~ service_name ~ "{" ~ service_element* ~ "}" ~ ";"? }
+    let mut service = Service::default();
+    for p in block.into_inner() {
+        match p.as_rule() {
+            Rule::service_name => service.name = p.as_str().to_string(),
+            Rule::service_element => {
+                for el in p.into_inner() {
+                    if el.as_rule() == Rule::rpc_method {
+                        service.methods.push(parse_rpc_method(el)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(service)
+}
+
+fn parse_rpc_method(pair: Pair<Rule>) -> Result<RpcMethod, ParseError> {
+    // rpc_method = { "rpc" ~ rpc_name ~ "(" ~ rpc_type ~ ")" ~ "returns" ~ "(" ~ rpc_type ~ ")" ~ ... }
+    let mut name = String::new();
+    let mut rpc_types: Vec<(bool, String)> = Vec::new();
+    let mut options = Vec::new();
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::rpc_name => name = p.as_str().to_string(),
+            Rule::option_entry => {
+                // option_entry = { "option" ~ option ~ ";" }
+                for o in p.into_inner() {
+                    if let Some(opt) = parse_option(o)? {
+                        options.push(opt);
+                    }
+                }
+            }
+            Rule::rpc_type => {
+                // rpc_type = { stream_marker? ~ type_reference }
+                let mut streaming = false;
+                let mut ty = String::new();
+                for t in p.into_inner() {
+                    match t.as_rule() {
+                        Rule::stream_marker => streaming = true,
+                        Rule::type_reference => ty = t.as_str().trim_start_matches('.').to_string(),
+                        _ => {}
+                    }
+                }
+                rpc_types.push((streaming, ty));
+            }
+            _ => {}
+        }
+    }
+    let mut it = rpc_types.into_iter();
+    let (client_streaming, input_type) = it.next().unwrap_or_default();
+    let (server_streaming, output_type) = it.next().unwrap_or_default();
+    Ok(RpcMethod {
+        name,
+        input_type,
+        output_type,
+        client_streaming,
+        server_streaming,
+        options,
+    })
 }
 
-fn parse_field(
-    pair: Pair<Rule>,
-    parent_scope: Option<&str>,
-    nested: Option<&std::collections::HashSet<String>>,
-) -> Option<Field> {
+fn parse_field(pair: Pair<Rule>) -> Result<Option<Field>, ParseError> {
     // field = { field_modifier? ~ type_reference ~ field_name ~ "=" ~ tag ~ field_options? ~ ";" }
+    // map = { "map" ~ "<" ~ map_key ~ "," ~ map_value ~ ">" ~ field_name ~ "=" ~ tag ~ field_options? ~ ";" }
+    let trailing_doc = trailing_comment(&pair);
+    let mut map_key: Option<ScalarType> = None;
+    let mut label: Option<FieldLabel> = None;
+    let mut options: Vec<(String, OptionValue)> = Vec::new();
     let mut ty_opt: Option<FieldType> = None;
     let mut name_opt: Option<String> = None;
     let mut order_opt: Option<u32> = None;
 
     for p in pair.into_inner() {
         match p.as_rule() {
-            Rule::field_modifier => { /* ignored in IR */ }
+            Rule::field_modifier => label = parse_field_label(p.as_str()),
             Rule::type_reference => ty_opt = Some(parse_type_reference(p)),
+            Rule::map_key => map_key = Some(parse_scalar_type(p.as_str())),
+            Rule::map_value => ty_opt = p.into_inner().next().map(parse_type_reference),
             Rule::field_name => name_opt = Some(p.as_str().to_string()),
-            Rule::tag => {
-                let n = parse_integer_value(p);
-                order_opt = u32::try_from(n).ok();
-            }
+            Rule::tag => order_opt = Some(parse_tag(p.as_str())?),
+            Rule::field_options => options = parse_field_options(p)?,
             _ => {}
         }
     }
 
-    // Qualify custom type if it references a nested declaration in current scope
-    if let (Some(FieldType::Custom(tn)), Some(scope)) = (&mut ty_opt, parent_scope)
-        && let Some(nested_set) = nested
-        && !tn.contains('.')
-        && nested_set.contains(tn)
+    let json_name = options.iter().find_map(|(n, v)| match v {
+        OptionValue::Str(s) if n == "json_name" => Some(s.clone()),
+        _ => None,
+    });
+
+    let features = features(&options);
+    let ty_opt = match map_key {
+        Some(key) => ty_opt.map(|value| FieldType::Map {
+            key,
+            value: Box::new(value),
+        }),
+        None => ty_opt,
+    };
+
+    Ok(match (ty_opt, name_opt, order_opt) {
+        (Some(ty), Some(name), Some(order)) => Some(Field {
+            ty,
+            name,
+            order,
+            label,
+            trailing_doc,
+            options,
+            json_name,
+            oneof: None,
+            features,
+        }),
+        _ => None,
+    })
+}
+
+// Comments are silent in the grammar, so look at the raw source following the
+// construct: a `//` or `/* */` comment on the same line is its trailing doc.
+fn trailing_comment(pair: &Pair<Rule>) -> Option<String> {
+    let rest = &pair.get_input()[pair.as_span().end()..];
+    let line = rest.split(['\r', '\n']).next().unwrap_or("").trim_start();
+    let text = if let Some(c) = line.strip_prefix("//") {
+        c
+    } else if let Some(c) = line.strip_prefix("/*") {
+        c.split("*/").next().unwrap_or(c)
+    } else {
+        return None;
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+// The comment directly above the construct: consecutive `//` lines or a
+// `/* */` block ending on the previous line. Lines are joined with '\n'.
+fn leading_comment(pair: &Pair<Rule>) -> Option<String> {
+    let before = &pair.get_input()[..pair.as_span().start()];
+    let (before, same_line) = before.rsplit_once('\n').unwrap_or(("", before));
+    if !same_line.trim().is_empty() {
+        return None;
+    }
+    let before = before.trim_end_matches([' ', '\t', '\r']);
+    let last_line = before.rsplit('\n').next().unwrap_or(before).trim();
+    let mut doc: Vec<&str> = Vec::new();
+    if let Some(inner) = last_line
+        .ends_with("*/")
+        .then(|| &before[..before.len() - 2])
     {
-        *tn = qualify(Some(scope), tn);
+        // block comments don't nest, so the opener is the first `/*` after
+        // the previous `*/`; it must start its line, otherwise it trails code
+        let from = inner.rfind("*/").map_or(0, |i| i + 2);
+        let open = from + inner[from..].find("/*")?;
+        let line_start = inner[..open].rfind('\n').map_or(0, |i| i + 1);
+        if !inner[line_start..open].trim().is_empty() {
+            return None;
+        }
+        let body = inner[open + 2..].trim_start_matches('*');
+        doc.extend(
+            body.lines()
+                .map(|l| l.trim().trim_start_matches('*').trim()),
+        );
+    } else {
+        for line in before.lines().rev().map(str::trim) {
+            let Some(c) = line.strip_prefix("//") else {
+                break;
+            };
+            doc.push(c.trim_start_matches('/').trim());
+        }
+        doc.reverse();
+    }
+    join_doc(doc)
+}
+
+// Comments at the top of the file, before any statement. These are usually a
+// license header rather than the doc of the first declaration, so they are
+// kept apart. Blank lines between comments are preserved.
+fn file_comment(input: &str) -> Option<String> {
+    let mut doc: Vec<&str> = Vec::new();
+    let mut lines = input.trim_start_matches('\u{feff}').lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if let Some(c) = line.strip_prefix("//") {
+            doc.push(c.trim_start_matches('/').trim());
+        } else if let Some(mut c) = line.strip_prefix("/*") {
+            // a block runs until the line holding `*/`; bare delimiter lines
+            // (`/**`, ` */`) add nothing
+            let mut first = true;
+            loop {
+                let (body, closed) = match c.split_once("*/") {
+                    Some((body, _)) => (body, true),
+                    None => (c, false),
+                };
+                let text = body.trim().trim_start_matches('*').trim();
+                if !(text.is_empty() && (first || closed)) {
+                    doc.push(text);
+                }
+                first = false;
+                if closed {
+                    break;
+                }
+                let Some(next) = lines.next() else { break };
+                c = next;
+            }
+        } else if line.is_empty() {
+            doc.push("");
+        } else {
+            break;
+        }
+    }
+    join_doc(doc)
+}
+
+fn join_doc(mut doc: Vec<&str>) -> Option<String> {
+    while doc.first() == Some(&"") {
+        doc.remove(0);
     }
+    while doc.last() == Some(&"") {
+        doc.pop();
+    }
+    (!doc.is_empty()).then(|| doc.join("\n"))
+}
+
+fn parse_tag(s: &str) -> Result<u32, ParseError> {
+    let n = parse_int(s).map_err(|e| invalid_number(s, e))?;
+    u32::try_from(n)
+        .map_err(|_| ParseError::InvalidNumber(format!("field tag `{}` is out of range", s)))
+}
 
-    match (ty_opt, name_opt, order_opt) {
-        (Some(ty), Some(name), Some(order)) => Some(Field { ty, name, order }),
+fn parse_field_label(s: &str) -> Option<FieldLabel> {
+    match s {
+        "optional" => Some(FieldLabel::Optional),
+        "required" => Some(FieldLabel::Required),
+        "repeated" => Some(FieldLabel::Repeated),
         _ => None,
     }
 }
 
 fn parse_type_reference(pair: Pair<Rule>) -> FieldType {
     // type_reference = { scalar_type | _dot? ~ full_ident }
-    // If it's a scalar, inner will include a scalar_type; otherwise, we can use the full string slice.
+    // The grammar decides: a scalar is a scalar_type token, anything else a custom type.
     let mut inners = pair.clone().into_inner();
     if let Some(first) = inners.next()
         && first.as_rule() == Rule::scalar_type
@@ -327,28 +833,222 @@ fn parse_scalar_type(s: &str) -> ScalarType {
         "bool" => ScalarType::Bool,
         "string" => ScalarType::String,
         "bytes" => ScalarType::Bytes,
-        _ => ScalarType::String, // fallback shouldn't happen
+        other => unreachable!("`{}` is not a scalar_type", other),
     }
 }
 
-fn parse_integer_value(pair: Pair<Rule>) -> u64 {
-    // integer_value = dec|hex|oct with optional minus; return as unsigned (negative saturates to 0)
-    let s = pair.as_str();
-    let (neg, rest) = if let Some(stripped) = s.strip_prefix('-') {
-        (true, stripped)
-    } else {
-        (false, s)
+fn parse_field_options(pair: Pair<Rule>) -> Result<Vec<(String, OptionValue)>, ParseError> {
+    // field_options = { "[" ~ option ~ ("," ~ option)* ~ "]" }
+    let mut options = Vec::new();
+    for p in pair.into_inner() {
+        if p.as_rule() == Rule::option
+            && let Some(opt) = parse_option(p)?
+        {
+            options.push(opt);
+        }
+    }
+    Ok(options)
+}
+
+// Edition `features.<name>` entries among `options`, keyed by feature name
+fn features(options: &[(String, OptionValue)]) -> HashMap<String, OptionValue> {
+    options
+        .iter()
+        .filter_map(|(name, value)| {
+            let feature = name.strip_prefix("features.")?;
+            Some((feature.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+// `option features.<name> = ...;`
+fn is_features_option(pair: &Pair<Rule>) -> bool {
+    pair.clone()
+        .into_inner()
+        .flat_map(|o| o.into_inner())
+        .any(|p| p.as_rule() == Rule::field_reference && p.as_str().starts_with("features."))
+}
+
+fn parse_option(pair: Pair<Rule>) -> Result<Option<(String, OptionValue)>, ParseError> {
+    // option = { field_reference ~ "=" ~ option_value }
+    let mut name: Option<String> = None;
+    let mut value: Option<OptionValue> = None;
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::field_reference => {
+                name = Some(p.as_str().split_whitespace().collect::<String>());
+            }
+            Rule::option_value => value = parse_option_value(p)?,
+            _ => {}
+        }
+    }
+    Ok(name.zip(value))
+}
+
+fn parse_option_value(pair: Pair<Rule>) -> Result<Option<OptionValue>, ParseError> {
+    // option_value = { float_value | integer_value | boolean_value | string_value | ident | text_format }
+    let Some(inner) = pair.into_inner().next() else {
+        return Ok(None);
+    };
+    let s = inner.as_str();
+    Ok(match inner.as_rule() {
+        Rule::integer_value => Some(OptionValue::Int(
+            parse_int(s).map_err(|e| invalid_number(s, e))?,
+        )),
+        Rule::float_value => s.parse::<f64>().ok().map(OptionValue::Float),
+        Rule::boolean_value => Some(OptionValue::Bool(s == "true")),
+        Rule::string_value => Some(OptionValue::Str(unescape_string(s))),
+        Rule::ident => Some(OptionValue::Ident(s.to_string())),
+        Rule::text_format => Some(OptionValue::Aggregate(s[1..s.len() - 1].trim().to_string())),
+        _ => None,
+    })
+}
+
+// Turn a quoted string_value (either quote style) into its actual text,
+// resolving C-style, \xNN, \uNNNN and octal escapes.
+fn unescape_string(literal: &str) -> String {
+    let body = &literal[1..literal.len() - 1];
+    let mut out: Vec<u8> = Vec::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let Some(e) = chars.next() else { break };
+        match e {
+            'a' => out.push(0x07),
+            'b' => out.push(0x08),
+            'f' => out.push(0x0c),
+            'n' => out.push(b'\n'),
+            'r' => out.push(b'\r'),
+            't' => out.push(b'\t'),
+            'v' => out.push(0x0b),
+            'x' | 'X' => {
+                let hex: String = (0..2).filter_map(|_| chars.next()).collect();
+                out.push(u8::from_str_radix(&hex, 16).unwrap_or(0));
+            }
+            'u' => {
+                let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                let ch = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            }
+            '0'..='7' => {
+                let mut oct = e.to_string();
+                while oct.len() < 3
+                    && let Some(d) = chars.next_if(|d| ('0'..='7').contains(d))
+                {
+                    oct.push(d);
+                }
+                out.push(u8::from_str_radix(&oct, 8).unwrap_or(0));
+            }
+            // \\, \', \", \? and anything else map to the escaped character
+            other => {
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Parse a proto integer literal: decimal, `0x` hex or `0`-prefixed octal,
+/// with an optional leading minus. Overflow and malformed digits are errors.
+pub fn parse_int(s: &str) -> Result<i64, ParseIntError> {
+    let (neg, rest) = match s.strip_prefix('-') {
+        Some(stripped) => (true, stripped),
+        None => (false, s),
     };
-    let val: i128 = if rest.starts_with("0x") || rest.starts_with("0X") {
-        i128::from_str_radix(&rest[2..], 16).unwrap_or(0)
+    let (radix, digits) = if let Some(hex) = rest.strip_prefix("0x").or(rest.strip_prefix("0X")) {
+        (16, hex)
     } else if rest.starts_with('0') && rest.len() > 1 {
-        // Octal per grammar
-        i128::from_str_radix(&rest[1..], 8).unwrap_or(0)
+        (8, &rest[1..])
+    } else {
+        (10, rest)
+    };
+    // Parse with the sign attached so i64::MIN is representable
+    if neg {
+        i64::from_str_radix(&format!("-{}", digits), radix)
     } else {
-        rest.parse::<i128>().unwrap_or(0)
+        i64::from_str_radix(digits, radix)
+    }
+}
+
+// extensions = { "extensions" ~ range ~ ("," ~ range)* ~ ";" }
+fn parse_extension_ranges(extensions: Pair<Rule>, message: &mut Message) -> Result<(), ParseError> {
+    let field_number = |s: &str| -> Result<u32, ParseError> {
+        let n = parse_int(s).map_err(|e| invalid_number(s, e))?;
+        u32::try_from(n).map_err(|_| {
+            ParseError::InvalidNumber(format!("extensions `{}` is not a field number", s))
+        })
+    };
+    for r in extensions.into_inner() {
+        let open_ended = r.as_str().ends_with("max");
+        let mut from = None;
+        let mut to = None;
+        for bound in r.into_inner() {
+            match bound.as_rule() {
+                Rule::range_from => from = Some(field_number(bound.as_str())?),
+                Rule::range_to => to = Some(field_number(bound.as_str())?),
+                _ => {}
+            }
+        }
+        if let Some(from) = from {
+            let to = if open_ended {
+                crate::validate::MAX_TAG
+            } else {
+                to.unwrap_or(from)
+            };
+            message.extension_ranges.push(from..=to);
+        }
+    }
+    Ok(())
+}
+
+// reserved = { "reserved" ~ (range ~ ("," ~ range)* | reserved_field_name ~ ...) ~ ";" }
+fn parse_enum_reserved(reserved: Pair<Rule>, en: &mut Enum) -> Result<(), ParseError> {
+    let enum_number = |s: &str| -> Result<i32, ParseError> {
+        let n = parse_int(s).map_err(|e| invalid_number(s, e))?;
+        i32::try_from(n).map_err(|_| {
+            ParseError::InvalidNumber(format!("reserved `{}` is out of int32 range", s))
+        })
     };
-    let signed = if neg { -val } else { val };
-    if signed < 0 { 0 } else { signed as u64 }
+    for r in reserved.into_inner() {
+        match r.as_rule() {
+            Rule::range => {
+                let open_ended = r.as_str().ends_with("max");
+                let mut from = None;
+                let mut to = None;
+                for bound in r.into_inner() {
+                    match bound.as_rule() {
+                        Rule::range_from => from = Some(enum_number(bound.as_str())?),
+                        Rule::range_to => to = Some(enum_number(bound.as_str())?),
+                        _ => {}
+                    }
+                }
+                if let Some(from) = from {
+                    let to = if open_ended {
+                        i32::MAX
+                    } else {
+                        to.unwrap_or(from)
+                    };
+                    en.reserved_numbers.push(from..=to);
+                }
+            }
+            Rule::reserved_field_name => en.reserved_names.push(unescape_string(r.as_str())),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn invalid_number(literal: &str, e: ParseIntError) -> ParseError {
+    ParseError::InvalidNumber(format!("`{}`: {}", literal, e))
 }
 
 // Test module.
@@ -356,6 +1056,7 @@ fn parse_integer_value(pair: Pair<Rule>) -> u64 {
 mod tests {
     use super::*;
     use std::fs;
+    use std::num::IntErrorKind;
 
     fn get_message<'a>(model: &'a ProtoModel, name: &str) -> &'a Message {
         model
@@ -396,6 +1097,8 @@ mod tests {
         assert_eq!(f("name").order, 2);
         assert_eq!(f("items").order, 3);
         assert!(matches!(f("items").ty, FieldType::Custom(ref s) if s == "OrderItem"));
+        assert_eq!(f("items").label, Some(FieldLabel::Repeated));
+        assert_eq!(f("id").label, None);
         assert_eq!(f("shipping_address").order, 4);
         assert!(
             matches!(f("shipping_address").ty, FieldType::Custom(ref s) if s == "Order.Address")
@@ -482,8 +1185,1546 @@ mod tests {
     }
 
     #[test]
-    fn missing_file_returns_io_error() {
-        let result = parse_proto_file("tests/resources/__missing.proto");
-        assert!(matches!(result, Err(ParseError::Io(_))));
+    fn captures_trailing_comments() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message User {
+                // leading comment is not a trailing doc
+                int32 id = 1; // the primary key
+                string name = 2;
+            }
+            enum Kind {
+                KIND_UNKNOWN = 0; /* default */
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let user = get_message(&model, "User");
+        assert_eq!(
+            user.fields[0].trailing_doc.as_deref(),
+            Some("the primary key")
+        );
+        assert_eq!(user.fields[1].trailing_doc, None);
+        let kind = get_enum(&model, "Kind");
+        assert_eq!(kind.values[0].trailing_doc.as_deref(), Some("default"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trips_complex_proto() {
+        let model = parse_proto_file("../java-generator/tests/resources/complex.proto")
+            .expect("parse failed");
+        let json = model_to_json(&model).unwrap();
+        assert_eq!(model_from_json(&json).unwrap(), model);
+        assert!(model_from_json("{\"syntax\": \"Proto4\"}").is_err());
+    }
+
+    #[test]
+    fn summarizes_complex_proto() {
+        let model = parse_proto_file("../java-generator/tests/resources/complex.proto")
+            .expect("parse failed");
+        let summary = model.summary();
+        assert_eq!(summary.messages, 4);
+        assert_eq!(summary.enums, 1);
+        assert_eq!(summary.fields, 17);
+        assert_eq!(summary.nested_types, 0);
+        assert_eq!(
+            summary.scalar_types.into_iter().collect::<Vec<_>>(),
+            vec![
+                ScalarType::Double,
+                ScalarType::Int32,
+                ScalarType::Int64,
+                ScalarType::String
+            ]
+        );
+
+        let nested = parse_proto_file("tests/resources/order.proto")
+            .unwrap()
+            .summary();
+        assert_eq!(nested.nested_types, 2);
+    }
+
+    #[test]
+    fn debug_tree_outlines_order_proto() {
+        let model = parse_proto_file("tests/resources/order.proto").unwrap();
+        assert_eq!(
+            model.debug_tree(),
+            "package me.alekseinovikov.proto (proto3)
+  message Order
+    id: int32 = 1
+    name: string = 2
+    items: repeated OrderItem = 3
+    shipping_address: Order.Address = 4
+    status: Order.Status = 5
+    message Address
+      street: string = 1
+      city: string = 2
+    enum Status
+      NEW = 0
+      PAID = 1
+      SHIPPED = 2
+  message OrderItem
+    name: string = 1
+    count: optional int64 = 2
+    type: OrderItemType = 3
+    price_decimal: double = 4 (oneof pricing)
+    price_cents: int64 = 5 (oneof pricing)
+  enum OrderItemType
+    REGULAR = 0
+    DISCOUNT = 1
+"
+        );
+    }
+
+    #[test]
+    fn parses_default_values_by_kind() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto2";
+            enum Color {
+                RED = 0;
+                GREEN = 1;
+            }
+            message Paint {
+                optional Color color = 1 [default = GREEN];
+                optional string label = 2 [default = "GREEN"];
+                optional int32 coats = 3 [default = -2];
+                optional double gloss = 4 [default = 0.5, deprecated = true];
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let paint = get_message(&model, "Paint");
+        let default = |n: &str| {
+            paint
+                .fields
+                .iter()
+                .find(|f| f.name == n)
+                .and_then(|f| f.default_value())
+                .cloned()
+        };
+        assert_eq!(default("color"), Some(OptionValue::Ident("GREEN".into())));
+        assert_eq!(default("label"), Some(OptionValue::Str("GREEN".into())));
+        assert_eq!(default("coats"), Some(OptionValue::Int(-2)));
+        assert_eq!(default("gloss"), Some(OptionValue::Float(0.5)));
+        assert_eq!(
+            paint.fields[3].options[1],
+            ("deprecated".to_string(), OptionValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn parse_int_handles_bases_and_errors() {
+        assert_eq!(parse_int("42").unwrap(), 42);
+        assert_eq!(parse_int("-42").unwrap(), -42);
+        assert_eq!(parse_int("0x1F").unwrap(), 31);
+        assert_eq!(parse_int("017").unwrap(), 15);
+        assert_eq!(parse_int("0").unwrap(), 0);
+        assert_eq!(parse_int("-0x8000000000000000").unwrap(), i64::MIN);
+        // overflow
+        assert!(parse_int("9223372036854775808").is_err());
+        // malformed hex / octal
+        assert!(parse_int("0xZZ").is_err());
+        assert!(parse_int("0x").is_err());
+        assert!(parse_int("09").is_err());
+    }
+
+    // `n` spelled in every base the grammar accepts
+    fn int_literals(n: i128) -> Vec<String> {
+        let sign = if n < 0 { "-" } else { "" };
+        let abs = n.unsigned_abs();
+        vec![
+            format!("{}{}", sign, abs),
+            format!("{}0x{:x}", sign, abs),
+            format!("{}0X{:X}", sign, abs),
+            format!("{}0{:o}", sign, abs),
+        ]
+    }
+
+    #[test]
+    fn parse_int_round_trips_boundaries_in_every_base() {
+        let in_range = [
+            0,
+            1,
+            -1,
+            i32::MIN as i64,
+            i32::MAX as i64,
+            u32::MAX as i64,
+            i64::MIN,
+            i64::MAX,
+        ];
+        for n in in_range {
+            for lit in int_literals(n as i128) {
+                assert_eq!(parse_int(&lit), Ok(n), "{}", lit);
+                let model = parse_proto_str(&format!("message M {{ option (n) = {}; }}", lit))
+                    .unwrap_or_else(|e| panic!("{}: {}", lit, e));
+                let options = &get_message(&model, "M").options;
+                assert_eq!(options[0].1, OptionValue::Int(n), "{}", lit);
+            }
+        }
+
+        // one past i64 either way, and the whole unsigned 64-bit top half
+        let out_of_range = [
+            i64::MAX as i128 + 1,
+            i64::MIN as i128 - 1,
+            u64::MAX as i128,
+            u64::MAX as i128 + 1,
+        ];
+        for n in out_of_range {
+            for lit in int_literals(n) {
+                let kind = parse_int(&lit).map_err(|e| *e.kind());
+                assert!(
+                    matches!(
+                        kind,
+                        Err(IntErrorKind::PosOverflow | IntErrorKind::NegOverflow)
+                    ),
+                    "{} gave {:?}",
+                    lit,
+                    kind
+                );
+                let parsed = parse_proto_str(&format!("message M {{ option (n) = {}; }}", lit));
+                assert!(
+                    matches!(parsed, Err(ParseError::InvalidNumber(_))),
+                    "{} gave {:?}",
+                    lit,
+                    parsed.map(|m| m.types)
+                );
+            }
+        }
+
+        // enum numbers narrow to int32: the bounds pass, one beyond is rejected
+        for (n, ok) in [
+            (i32::MIN as i128, true),
+            (i32::MAX as i128, true),
+            (i32::MIN as i128 - 1, false),
+            (i32::MAX as i128 + 1, false),
+        ] {
+            for lit in int_literals(n) {
+                let parsed = parse_proto_str(&format!("enum E {{ ZERO = 0; EDGE = {}; }}", lit));
+                match parsed {
+                    Ok(model) if ok => {
+                        assert_eq!(get_enum(&model, "E").values[1].number as i128, n, "{}", lit)
+                    }
+                    Err(ParseError::InvalidNumber(_)) if !ok => {}
+                    other => panic!("{} gave {:?}", lit, other.map(|m| m.types)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn integer_literals_parse_in_protos() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Tags {
+                int32 a = 0x10;
+                int32 b = 017;
+            }
+            enum Sign {
+                ZERO = 0;
+                MINUS_ONE = -1;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let tags = get_message(&model, "Tags");
+        assert_eq!(tags.fields[0].order, 16);
+        assert_eq!(tags.fields[1].order, 15);
+        assert_eq!(get_enum(&model, "Sign").values[1].number, -1);
+
+        let overflow = parse_proto_str("message M { int32 a = 99999999999999999999; }");
+        assert!(matches!(overflow, Err(ParseError::InvalidNumber(_))));
+        let negative = parse_proto_str("message M { int32 a = -1; }");
+        assert!(matches!(negative, Err(ParseError::InvalidNumber(_))));
+    }
+
+    #[test]
+    fn unescapes_string_option_values() {
+        let model = parse_proto_str(
+            r#"
+            message Doc {
+                string text = 1 [default = "line1\nline2", (my.quote) = 'say \"hi\"\t\x41\101\u00e9'];
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let field = &get_message(&model, "Doc").fields[0];
+        assert_eq!(
+            field.default_value(),
+            Some(&OptionValue::Str("line1\nline2".to_string()))
+        );
+        assert_eq!(
+            field.options[1],
+            (
+                "(my.quote)".to_string(),
+                OptionValue::Str("say \"hi\"\tAA\u{e9}".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn parses_services_and_rpcs() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            service OrderService {
+                rpc GetOrder (OrderRequest) returns (OrderResponse);
+                rpc Watch (.shop.OrderRequest) returns (stream streamEvent) {}
+            }
+        "#,
+        )
+        .expect("parse failed");
+        assert_eq!(model.services.len(), 1);
+        let svc = &model.services[0];
+        assert_eq!(svc.name, "OrderService");
+        assert_eq!(
+            svc.methods[0],
+            RpcMethod {
+                name: "GetOrder".into(),
+                input_type: "OrderRequest".into(),
+                output_type: "OrderResponse".into(),
+                client_streaming: false,
+                server_streaming: false,
+                options: vec![],
+            }
+        );
+        assert_eq!(svc.methods[1].input_type, "shop.OrderRequest");
+        assert_eq!(svc.methods[1].output_type, "streamEvent");
+        assert!(svc.methods[1].server_streaming);
+        assert!(!svc.methods[1].client_streaming);
+    }
+
+    #[test]
+    fn parses_top_level_extend_blocks() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto2";
+            message Foo {
+                optional int32 id = 1;
+                extensions 100 to 199;
+            }
+            extend Foo {
+                optional int32 bar = 100;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        assert_eq!(model.extensions.len(), 1);
+        let ext = &model.extensions[0];
+        assert_eq!(ext.extendee, "Foo");
+        assert_eq!(ext.fields.len(), 1);
+        assert_eq!(ext.fields[0].name, "bar");
+        assert_eq!(ext.fields[0].order, 100);
+        assert_eq!(ext.fields[0].label, Some(FieldLabel::Optional));
+        assert!(matches!(
+            ext.fields[0].ty,
+            FieldType::Scalar(ScalarType::Int32)
+        ));
+    }
+
+    #[test]
+    fn parses_message_extension_ranges() {
+        let src = r#"syntax = "proto2";
+
+message Foo {
+  optional int32 id = 1;
+  optional int32 clash = 150;
+  extensions 100 to 199, 250;
+  extensions 1000 to max;
+}
+"#;
+        let model = parse_proto_str(src).expect("parse failed");
+        let foo = get_message(&model, "Foo");
+        assert_eq!(
+            foo.extension_ranges,
+            vec![100..=199, 250..=250, 1000..=536_870_911]
+        );
+        assert!(foo.is_extension_number(150));
+        assert!(!foo.is_extension_number(200));
+
+        // like reserved numbers, the ranges are off limits to fields
+        assert_eq!(
+            validate(&model).unwrap_err(),
+            vec![ValidationError::FieldInExtensionRange {
+                message: "Foo".into(),
+                field: "clash".into(),
+                tag: 150,
+            }]
+        );
+        let printed = model_to_proto(&model);
+        assert!(printed.contains("  extensions 100 to 199, 250, 1000 to max;\n"));
+        assert_eq!(parse_proto_str(&printed).unwrap(), model);
+    }
+
+    #[test]
+    fn validation_reports_semantic_errors() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            enum Color {
+                RED = 1;
+            }
+            message Paint {
+                int32 a = 1;
+                int32 b = 1;
+                int32 c = 19500;
+                Missing d = 2;
+                Color e = 3;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let errors = validate(&model).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::EnumZeroMissing {
+                    enum_name: "Color".into()
+                },
+                ValidationError::InvalidTag {
+                    message: "Paint".into(),
+                    field: "c".into(),
+                    tag: 19500
+                },
+                ValidationError::UnknownType {
+                    message: "Paint".into(),
+                    field: "d".into(),
+                    type_name: "Missing".into()
+                },
+                ValidationError::DuplicateTag {
+                    message: "Paint".into(),
+                    tag: 1
+                },
+            ]
+        );
+
+        let order = parse_proto_file("tests/resources/order.proto").unwrap();
+        assert_eq!(validate(&order), Ok(()));
+    }
+
+    #[test]
+    fn captures_aggregate_rpc_options() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            service OrderService {
+                rpc GetOrder (GetOrderRequest) returns (Order) {
+                    option (google.api.http) = {
+                        get: "/v1/orders/{id}"
+                        additional_bindings { get: "/v1/shops/orders/{id}" },
+                    };
+                }
+                rpc ListOrders (ListOrdersRequest) returns (ListOrdersResponse) {
+                    option deprecated = true;
+                }
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let methods = &model.services[0].methods;
+        assert_eq!(methods[0].options.len(), 1);
+        let (name, value) = &methods[0].options[0];
+        assert_eq!(name, "(google.api.http)");
+        match value {
+            OptionValue::Aggregate(body) => {
+                assert!(body.starts_with("get: \"/v1/orders/{id}\""));
+                assert!(body.contains("additional_bindings"));
+            }
+            other => panic!("expected aggregate, got {:?}", other),
+        }
+        assert_eq!(
+            methods[1].options,
+            vec![("deprecated".to_string(), OptionValue::Bool(true))]
+        );
+    }
+
+    #[test]
+    fn parses_files_with_bom_and_crlf() {
+        let source = "syntax = \"proto3\";\npackage demo;\n// comment\nmessage A {\n  int32 id = 1; // key\n}\n";
+        fs::create_dir_all("target/tmp").unwrap();
+
+        let mut with_bom = "\u{feff}".as_bytes().to_vec();
+        with_bom.extend_from_slice(source.as_bytes());
+        fs::write("target/tmp/bom.proto", with_bom).unwrap();
+        let model = parse_proto_file("target/tmp/bom.proto").expect("BOM file should parse");
+        assert_eq!(model.package.as_deref(), Some("demo"));
+        assert_eq!(get_message(&model, "A").fields[0].name, "id");
+
+        fs::write("target/tmp/crlf.proto", source.replace('\n', "\r\n")).unwrap();
+        let model = parse_proto_file("target/tmp/crlf.proto").expect("CRLF file should parse");
+        let field = &get_message(&model, "A").fields[0];
+        assert_eq!(field.order, 1);
+        assert_eq!(field.trailing_doc.as_deref(), Some("key"));
+    }
+
+    #[test]
+    fn looks_up_fields_by_tag_and_name() {
+        let model = parse_proto_file("tests/resources/order.proto").unwrap();
+        let order = get_message(&model, "Order");
+        assert_eq!(
+            order.field_by_tag(4).map(|f| f.name.as_str()),
+            Some("shipping_address")
+        );
+        assert_eq!(order.field_by_name("status").map(|f| f.order), Some(5));
+        assert!(order.field_by_tag(42).is_none());
+        assert!(order.field_by_name("missing").is_none());
+    }
+
+    #[test]
+    fn validation_enforces_labels_per_syntax() {
+        let proto2 = parse_proto_str(
+            r#"
+            syntax = "proto2";
+            message Legacy {
+                optional int32 a = 1;
+                int32 b = 2;
+                oneof choice {
+                    string c = 3;
+                }
+                map<string, int32> counts = 4;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        assert_eq!(
+            validate(&proto2),
+            Err(vec![ValidationError::MissingLabel {
+                message: "Legacy".into(),
+                field: "b".into()
+            }])
+        );
+
+        let proto3 = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Modern {
+                required int32 a = 1;
+                optional int32 b = 2;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        assert_eq!(
+            validate(&proto3),
+            Err(vec![ValidationError::RequiredInProto3 {
+                message: "Modern".into(),
+                field: "a".into()
+            }])
+        );
+    }
+
+    #[test]
+    fn missing_file_returns_io_error() {
+        let result = parse_proto_file("tests/resources/__missing.proto");
+        match result {
+            Err(ParseError::InFile { path, error }) => {
+                assert_eq!(path, Path::new("tests/resources/__missing.proto"));
+                assert!(matches!(*error, ParseError::Io(_)));
+            }
+            other => panic!("expected an IO error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn enum_numbers_are_checked_against_int32_range() {
+        let model = parse_proto_str(
+            "enum Wide { ZERO = 0; MAX = 0x7fffffff; NEG = -0x10; MIN = -2147483648; }",
+        )
+        .expect("parse failed");
+        let wide = get_enum(&model, "Wide");
+        assert_eq!(wide.values[1].number, i32::MAX);
+        assert_eq!(wide.values[2].number, -16);
+        assert_eq!(wide.values[3].number, i32::MIN);
+
+        let too_big = parse_proto_str("enum E { ZERO = 0; BIG = 0x80000000; }");
+        assert!(matches!(too_big, Err(ParseError::InvalidNumber(_))));
+        let too_small = parse_proto_str("enum E { ZERO = 0; SMALL = -2147483649; }");
+        assert!(matches!(too_small, Err(ParseError::InvalidNumber(_))));
+    }
+
+    #[test]
+    fn model_to_proto_round_trips_field_options_in_order() {
+        let src = r#"
+            syntax = "proto2";
+            package shop;
+            message Order {
+                optional int32 id = 1 [deprecated = true, json_name = "x"]; // order id
+                optional string note = 2 [json_name = "n", default = "a\"b", (my.opt) = 1.5];
+                oneof payment {
+                    string card = 3;
+                    Order.Cash cash = 4;
+                }
+                message Cash { required int64 cents = 1; }
+                enum Status { NEW = 0; DONE = -1; }
+            }
+            service Orders {
+                rpc Watch (Order) returns (stream Order) { option deprecated = true; }
+            }
+        "#;
+        let model = parse_proto_str(src).expect("parse failed");
+        let printed = model_to_proto(&model);
+        assert!(printed.contains(
+            "optional int32 id = 1 [deprecated = true, json_name = \"x\"]; // order id\n"
+        ));
+        assert!(printed.contains(r#"[json_name = "n", default = "a\"b", (my.opt) = 1.5];"#));
+
+        let reparsed = parse_proto_str(&printed).expect("printed proto reparses");
+        assert_eq!(reparsed, model);
+        let id = get_message(&reparsed, "Order").field_by_name("id").unwrap();
+        let names: Vec<&str> = id.options.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["deprecated", "json_name"]);
+    }
+
+    #[test]
+    fn json_key_prefers_json_name_over_lower_camel_case() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+                string display_name = 1;
+                int64 created_at_2x = 2 [json_name = "created"];
+                string id = 3;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let keys: Vec<String> = get_message(&model, "Order")
+            .fields
+            .iter()
+            .map(|f| f.json_key())
+            .collect();
+        assert_eq!(keys, ["displayName", "created", "id"]);
+    }
+
+    #[test]
+    fn oneof_members_keep_message_types_and_options() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Payment {
+                oneof method {
+                    option (my.oneof_opt) = true;
+                    Card card = 1 [deprecated = true];
+                    Wallet wallet = 2;
+                    string voucher = 3;
+                }
+                message Card { string number = 1; }
+            }
+            message Wallet { string id = 1; }
+        "#,
+        )
+        .expect("parse failed");
+        let payment = get_message(&model, "Payment");
+        assert_eq!(payment.fields.len(), 3);
+        assert!(
+            payment
+                .fields
+                .iter()
+                .all(|f| f.oneof.as_deref() == Some("method"))
+        );
+
+        let card = payment.field_by_name("card").unwrap();
+        // nested message reference is qualified with its enclosing message
+        assert_eq!(card.ty, FieldType::Custom("Payment.Card".into()));
+        assert_eq!(
+            card.options,
+            vec![("deprecated".to_string(), OptionValue::Bool(true))]
+        );
+        let wallet = payment.field_by_name("wallet").unwrap();
+        assert_eq!(wallet.ty, FieldType::Custom("Wallet".into()));
+        assert_eq!(
+            model.resolve_type_name(&payment.name, "Wallet").as_deref(),
+            Some("Wallet")
+        );
+    }
+
+    #[test]
+    fn deeply_nested_types_qualify_and_resolve() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package deep;
+            message A {
+                B.C.D deepest = 1;
+                message B {
+                    message C {
+                        message D {
+                            string leaf = 1;
+                            B back = 2;
+                            Kind kind = 3;
+                            enum Kind { NONE = 0; }
+                        }
+                        D d = 1;
+                    }
+                }
+            }
+            message Z { deep.A.B.C.D d = 1; }
+        "#,
+        )
+        .expect("parse failed");
+        let d = get_message(&model, "A.B.C.D");
+        assert_eq!(d.fields[0].name, "leaf");
+        assert_eq!(d.fields[2].ty, FieldType::Custom("A.B.C.D.Kind".into()));
+        assert_eq!(get_enum(&model, "A.B.C.D.Kind").values[0].name, "NONE");
+        let c = get_message(&model, "A.B.C");
+        assert_eq!(c.fields[0].ty, FieldType::Custom("A.B.C.D".into()));
+
+        let resolve = |scope: &str, name: &str| model.resolve_type_name(scope, name);
+        assert_eq!(resolve("A", "B.C.D").as_deref(), Some("A.B.C.D"));
+        // `B` from inside D is found by walking outwards to A
+        assert_eq!(resolve("A.B.C.D", "B").as_deref(), Some("A.B"));
+        assert_eq!(resolve("Z", "deep.A.B.C.D").as_deref(), Some("A.B.C.D"));
+        let nested: Vec<&str> = model.nested_types("A.B.C").map(|t| t.name()).collect();
+        assert_eq!(nested, ["A.B.C.D"]);
+    }
+
+    #[test]
+    fn scalar_wire_compatibility_groups() {
+        use ScalarType::*;
+        let compatible = [
+            (Int32, Int64),
+            (Uint64, Bool),
+            (Int32, Uint32),
+            (Sint32, Sint64),
+            (Fixed32, Sfixed32),
+            (Fixed64, Sfixed64),
+            (String, Bytes),
+            (Double, Double),
+        ];
+        for (a, b) in &compatible {
+            assert!(a.wire_compatible_with(b), "{:?} ~ {:?}", a, b);
+            assert!(b.wire_compatible_with(a), "{:?} ~ {:?}", b, a);
+        }
+        let incompatible = [
+            (Int32, Sint32),
+            (Fixed32, Fixed64),
+            (Int64, Fixed64),
+            (Float, Double),
+            (String, Int32),
+            (Bool, Sint64),
+        ];
+        for (a, b) in &incompatible {
+            assert!(!a.wire_compatible_with(b), "{:?} !~ {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn warnings_flag_misspelled_scalars_and_unresolved_types() {
+        let result = parse_proto_str_with_warnings(
+            r#"
+            syntax = "proto3";
+            message Order {
+                int23 count = 1;
+                Strng name = 2;
+                Customer customer = 3;
+                Item item = 4;
+            }
+            message Item { string sku = 1; }
+        "#,
+        )
+        .expect("parse failed");
+        assert_eq!(result.model.types.len(), 2);
+        let messages: Vec<String> = result.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Order.count: type `int23` looks like a misspelling of `int32`",
+                "Order.name: type `Strng` looks like a misspelling of `string`",
+                "Order.customer: type `Customer` is not declared and nothing is imported",
+            ]
+        );
+
+        // with an import the type may come from elsewhere
+        let imported =
+            parse_proto_str_with_warnings("import \"c.proto\"; message M { Customer c = 1; }")
+                .unwrap();
+        assert!(imported.warnings.is_empty());
+    }
+
+    #[test]
+    fn syntax_less_files_are_proto2() {
+        let legacy =
+            parse_proto_str("package old; message M { int32 a = 1; optional int32 b = 2; }")
+                .expect("parse failed");
+        assert_eq!(legacy.syntax, ProtoSyntax::Proto2);
+        // proto2 label rules apply: `a` has no label
+        assert_eq!(
+            validate(&legacy),
+            Err(vec![ValidationError::MissingLabel {
+                message: "M".into(),
+                field: "a".into(),
+            }])
+        );
+
+        let proto3 = parse_proto_str("syntax = 'proto3'; message M { int32 a = 1; }").unwrap();
+        assert_eq!(proto3.syntax, ProtoSyntax::Proto3);
+        assert_eq!(validate(&proto3), Ok(()));
+    }
+
+    #[test]
+    fn enum_values_capture_options_and_leading_docs() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            enum Color {
+                UNKNOWN = 0; // trailing only
+                // Pure red.
+                // Prefer CRIMSON.
+                RED = 1 [deprecated = true];
+                /**
+                 * The new red.
+                 */
+                CRIMSON = 2;
+                BLUE = 3 [deprecated = false]; /* not a leading doc */
+                GREEN = 4;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let color = get_enum(&model, "Color");
+        let docs: Vec<Option<&str>> = color
+            .values
+            .iter()
+            .map(|v| v.leading_doc.as_deref())
+            .collect();
+        assert_eq!(
+            docs,
+            [
+                None,
+                Some("Pure red.\nPrefer CRIMSON."),
+                Some("The new red."),
+                None,
+                None
+            ]
+        );
+        let deprecated: Vec<bool> = color.values.iter().map(|v| v.is_deprecated()).collect();
+        assert_eq!(deprecated, [false, true, false, false, false]);
+        assert_eq!(
+            color.values[3].options,
+            [("deprecated".to_string(), OptionValue::Bool(false))]
+        );
+
+        let reparsed = parse_proto_str(&model_to_proto(&model)).unwrap();
+        assert_eq!(reparsed, model);
+    }
+
+    #[test]
+    fn enum_reserved_numbers_and_names() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            enum Status {
+                reserved 2, 9 to 11, 40 to max;
+                reserved "LEGACY";
+                UNKNOWN = 0;
+                ACTIVE = 1;
+                ARCHIVED = 10;
+                LEGACY = 3;
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let status = get_enum(&model, "Status");
+        assert_eq!(status.reserved_numbers, vec![2..=2, 9..=11, 40..=i32::MAX]);
+        assert_eq!(status.reserved_names, vec!["LEGACY".to_string()]);
+
+        assert_eq!(
+            validate(&model).unwrap_err(),
+            vec![
+                ValidationError::ReservedEnumNumber {
+                    enum_name: "Status".into(),
+                    value: "ARCHIVED".into(),
+                    number: 10
+                },
+                ValidationError::ReservedEnumName {
+                    enum_name: "Status".into(),
+                    value: "LEGACY".into()
+                },
+            ]
+        );
+
+        let reparsed = parse_proto_str(&model_to_proto(&model)).expect("printed proto reparses");
+        assert_eq!(reparsed, model);
+    }
+
+    #[test]
+    fn oneof_keeps_members_after_group() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto2";
+            message SearchResponse {
+                oneof result {
+                    string error = 1;
+                    group Hit = 2 {
+                        optional string url = 3;
+                    }
+                    Page page = 4;
+                    int32 count = 5;
+                }
+                message Page { optional int32 number = 1; }
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let resp = get_message(&model, "SearchResponse");
+        let members: Vec<(&str, u32)> = resp
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.order))
+            .collect();
+        assert_eq!(
+            members,
+            [("error", 1), ("hit", 2), ("page", 4), ("count", 5)]
+        );
+        assert!(
+            resp.fields
+                .iter()
+                .all(|f| f.oneof.as_deref() == Some("result"))
+        );
+        assert_eq!(
+            resp.field_by_name("hit").unwrap().ty,
+            FieldType::Custom("SearchResponse.Hit".into())
+        );
+        assert_eq!(
+            resp.field_by_name("page").unwrap().ty,
+            FieldType::Custom("SearchResponse.Page".into())
+        );
+        let hit = get_message(&model, "SearchResponse.Hit");
+        assert_eq!(hit.fields[0].name, "url");
+
+        let reparsed = parse_proto_str(&model_to_proto(&model)).expect("printed proto reparses");
+        assert_eq!(reparsed, model);
+    }
+
+    #[test]
+    fn large_message_qualifies_nested_references_in_one_pass() {
+        // Fields reference nested types declared both before and after them,
+        // plus same-named top-level types that must stay unqualified.
+        let mut src = String::from("syntax = \"proto3\";\nmessage Big {\n");
+        for i in 0..200 {
+            src.push_str(&format!("  message N{i} {{ string v = 1; }}\n"));
+        }
+        for i in 0..400 {
+            let tag = i + 1;
+            match i % 4 {
+                0 => src.push_str(&format!("  N{} n{i} = {tag};\n", i / 2)),
+                1 => src.push_str(&format!("  Late{} l{i} = {tag};\n", i / 4)),
+                2 => src.push_str(&format!("  Top t{i} = {tag};\n")),
+                _ => src.push_str(&format!("  oneof o{i} {{ N0 a{i} = {tag}; }}\n")),
+            }
+        }
+        for i in 0..100 {
+            src.push_str(&format!("  enum Late{i} {{ L{i}_ZERO = 0; }}\n"));
+        }
+        src.push_str("}\nmessage Top {}\n");
+
+        let model = parse_proto_str(&src).expect("parse failed");
+        let big = get_message(&model, "Big");
+        assert_eq!(big.fields.len(), 400);
+        for (i, f) in big.fields.iter().enumerate() {
+            let expected = match i % 4 {
+                0 => format!("Big.N{}", i / 2),
+                1 => format!("Big.Late{}", i / 4),
+                2 => "Top".to_string(),
+                _ => "Big.N0".to_string(),
+            };
+            assert_eq!(f.ty, FieldType::Custom(expected), "field {}", f.name);
+        }
+        assert_eq!(model.summary().nested_types, 300);
+        assert_eq!(parse_proto_str(&model_to_proto(&model)).unwrap(), model);
+    }
+
+    #[test]
+    fn known_field_options_are_typed() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto2";
+            message Blob {
+                optional int64 id = 1 [jstype = JS_STRING];
+                optional bytes data = 2 [ctype = CORD, lazy = true];
+                optional string name = 3 [ctype = UNKNOWN_KIND];
+            }
+        "#,
+        )
+        .expect("parse failed");
+        let blob = get_message(&model, "Blob");
+        let id = blob.field_by_name("id").unwrap();
+        assert_eq!(id.jstype(), Some(JsType::String));
+        assert_eq!(
+            id.option("jstype"),
+            Some(&OptionValue::Ident("JS_STRING".into()))
+        );
+        assert_eq!(id.ctype(), None);
+        assert!(!id.is_lazy());
+
+        let data = blob.field_by_name("data").unwrap();
+        assert_eq!(data.ctype(), Some(CType::Cord));
+        assert!(data.is_lazy());
+        assert_eq!(blob.field_by_name("name").unwrap().ctype(), None);
+    }
+
+    #[test]
+    fn pass_pipeline_strips_deprecated_and_renames_package() {
+        let mut model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop.v1;
+            message Order {
+                string id = 1;
+                string legacy = 2 [deprecated = true];
+                shop.v1.Status status = 3;
+                shop.v10.Other other = 4;
+            }
+            enum Status { NEW = 0; OLD = 1 [deprecated = true]; }
+            service Orders { rpc Get (.shop.v1.Order) returns (Order); }
+        "#,
+        )
+        .expect("parse failed");
+        PassPipeline::new()
+            .with(StripDeprecated)
+            .with(RenamePackage {
+                from: "shop.v1".into(),
+                to: "acme.shop".into(),
+            })
+            .run(&mut model);
+
+        assert_eq!(model.package.as_deref(), Some("acme.shop"));
+        let order = get_message(&model, "Order");
+        let types: Vec<&FieldType> = order.fields.iter().map(|f| &f.ty).collect();
+        assert_eq!(
+            types,
+            [
+                &FieldType::Scalar(ScalarType::String),
+                &FieldType::Custom("acme.shop.Status".into()),
+                // only whole package segments are renamed
+                &FieldType::Custom("shop.v10.Other".into()),
+            ]
+        );
+        assert_eq!(get_enum(&model, "Status").values.len(), 1);
+        assert_eq!(model.services[0].methods[0].input_type, "acme.shop.Order");
+    }
+
+    #[test]
+    fn resolve_field_types_splits_messages_and_enums() {
+        let mut model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            import "google/protobuf/timestamp.proto";
+            message Order {
+                Status status = 1;
+                Address shipping = 2;
+                OrderType type = 3;
+                google.protobuf.Timestamp created = 4;
+                message Address { string city = 1; }
+                enum Status { NEW = 0; }
+            }
+            enum OrderType { ONLINE = 0; }
+        "#,
+        )
+        .expect("parse failed");
+        ResolveFieldTypes.apply(&mut model);
+
+        let order = get_message(&model, "Order");
+        let types: Vec<&FieldType> = order.fields.iter().map(|f| &f.ty).collect();
+        assert_eq!(
+            types,
+            [
+                &FieldType::Enum("Order.Status".into()),
+                &FieldType::Message("Order.Address".into()),
+                &FieldType::Enum("OrderType".into()),
+                // declared in another file
+                &FieldType::Custom("google.protobuf.Timestamp".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_unmodeled_constructs() {
+        let src = r#"syntax = "proto3";
+option java_package = "com.acme";
+message Order {
+    string id = 1;
+    map<string, int32> counts = 2;
+    reserved 5;
+}
+"#;
+        // lenient by default: the option and reserved statement are dropped silently
+        assert!(parse_proto_str(src).is_ok());
+        let lenient = parse_proto_str_with_options(src, &ParseOptions::default());
+        assert_eq!(lenient.unwrap(), parse_proto_str(src).unwrap());
+
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        match parse_proto_str_with_options(src, &strict) {
+            Err(ParseError::Unsupported(items)) => assert_eq!(
+                items,
+                [
+                    "line 2: file option `option java_package = \"com.acme\";`",
+                    "line 6: message reserved statement `reserved 5;`",
+                ]
+            ),
+            other => panic!("expected Unsupported, got {:?}", other),
+        }
+        let modeled = "syntax = \"proto3\"; message A { string id = 1; map<string, int32> counts = 2; enum E { reserved 2; Z = 0; } }";
+        assert!(parse_proto_str_with_options(modeled, &strict).is_ok());
+    }
+
+    #[test]
+    fn message_dependencies_are_deduplicated() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message OrderItem { string sku = 1; }
+            message Order {
+              message Address { string city = 1; }
+              repeated OrderItem items = 1;
+              Address shipping = 2;
+              Address billing = 3;
+              int64 id = 4;
+            }
+            "#,
+        )
+        .unwrap();
+        let order = get_message(&model, "Order");
+        assert_eq!(order.dependencies(), ["OrderItem", "Order.Address"]);
+    }
+
+    #[test]
+    fn parses_map_fields() {
+        let src = r#"syntax = "proto3";
+
+message Order {
+  map<string, Order.Status> statuses = 1;
+  map<int64, Line> lines = 2 [deprecated = true];
+  map<bool, bytes> flags = 3;
+  enum Status {
+    NEW = 0;
+  }
+}
+
+message Line {
+  string sku = 1;
+}
+"#;
+        let mut model = parse_proto_str(src).expect("parse failed");
+        let order = get_message(&model, "Order");
+        let map = |key, value| FieldType::Map {
+            key,
+            value: Box::new(value),
+        };
+        assert_eq!(
+            order.fields[0].ty,
+            map(ScalarType::String, FieldType::Custom("Order.Status".into()))
+        );
+        assert_eq!(
+            order.fields[1].ty,
+            map(ScalarType::Int64, FieldType::Custom("Line".into()))
+        );
+        assert_eq!(order.fields[1].order, 2);
+        assert!(order.fields[1].is_deprecated());
+        assert_eq!(order.fields[2].label, None);
+        // value types count as references
+        assert_eq!(order.dependencies(), ["Order.Status", "Line"]);
+        assert_eq!(model.referrers("Line")[0].name, "Order");
+
+        assert_eq!(parse_proto_str(&model_to_proto(&model)).unwrap(), model);
+        assert!(model_to_proto(&model).contains("  map<string, Order.Status> statuses = 1;\n"));
+
+        ResolveFieldTypes.apply(&mut model);
+        assert_eq!(
+            get_message(&model, "Order").fields[0].ty,
+            map(ScalarType::String, FieldType::Enum("Order.Status".into()))
+        );
+        assert!(
+            model
+                .debug_tree()
+                .contains("    statuses: map<string, enum Order.Status> = 1\n")
+        );
+    }
+
+    #[test]
+    fn license_header_is_file_comment() {
+        let src = r#"/*
+ * Copyright 2025 Example Corp.
+ * Licensed under MIT.
+ */
+// SPDX-License-Identifier: MIT
+
+syntax = "proto3";
+
+// Not part of the header
+enum Kind {
+  // the default
+  KIND_UNSPECIFIED = 0;
+}
+message User { string name = 1; }
+"#;
+        let model = parse_proto_str(src).unwrap();
+        assert_eq!(
+            model.file_comment.as_deref(),
+            Some("Copyright 2025 Example Corp.\nLicensed under MIT.\nSPDX-License-Identifier: MIT")
+        );
+        let kind = get_enum(&model, "Kind");
+        assert_eq!(kind.values[0].leading_doc.as_deref(), Some("the default"));
+
+        let reparsed = parse_proto_str(&model_to_proto(&model)).unwrap();
+        assert_eq!(reparsed.file_comment, model.file_comment);
+        assert_eq!(
+            parse_proto_str("syntax = \"proto3\";")
+                .unwrap()
+                .file_comment,
+            None
+        );
+    }
+
+    #[test]
+    fn declared_type_names_include_nested() {
+        let model = parse_proto_file("tests/resources/order.proto").expect("parse failed");
+        let names = model.declared_type_names();
+        for name in [
+            "Order",
+            "Order.Address",
+            "Order.Status",
+            "OrderItem",
+            "OrderItemType",
+        ] {
+            assert!(names.contains(name), "missing {}", name);
+        }
+        assert_eq!(names.len(), model.types.len());
+    }
+
+    #[test]
+    fn custom_types_named_like_scalars() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message int32Pair { int32 a = 1; int32 b = 2; }
+            message string_list { repeated string items = 1; }
+            message String { string value = 1; }
+            message Holder {
+              int32Pair pair = 1;
+              string_list names = 2;
+              String wrapped = 3;
+              string plain = 4;
+            }
+            "#,
+        )
+        .unwrap();
+        let holder = get_message(&model, "Holder");
+        let types: Vec<&FieldType> = holder.fields.iter().map(|f| &f.ty).collect();
+        assert_eq!(
+            types,
+            [
+                &FieldType::Custom("int32Pair".into()),
+                &FieldType::Custom("string_list".into()),
+                &FieldType::Custom("String".into()),
+                &FieldType::Scalar(ScalarType::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn spaced_package_name_is_normalized() {
+        let model = parse_proto_str(
+            "syntax = \"proto3\";\npackage foo . bar\n  . /* sub */ baz ;\nmessage A {}",
+        )
+        .unwrap();
+        assert_eq!(model.package.as_deref(), Some("foo.bar.baz"));
+        let plain = parse_proto_str("syntax = \"proto3\"; package foo.bar.baz;").unwrap();
+        assert_eq!(plain.package, model.package);
+    }
+
+    #[test]
+    fn message_options_are_captured() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+              message CountsEntry {
+                option map_entry = true;
+                string key = 1;
+                int32 value = 2;
+              }
+              option deprecated = true;
+              option (acme.audit) = "orders";
+              repeated CountsEntry counts = 1;
+            }
+            "#,
+        )
+        .unwrap();
+        let entry = get_message(&model, "Order.CountsEntry");
+        assert_eq!(
+            entry.options,
+            [("map_entry".to_string(), OptionValue::Bool(true))]
+        );
+        assert!(entry.is_map_entry());
+        assert!(!entry.is_deprecated());
+
+        let order = get_message(&model, "Order");
+        assert!(order.is_deprecated());
+        assert!(!order.is_map_entry());
+        assert_eq!(
+            order.option("(acme.audit)"),
+            Some(&OptionValue::Str("orders".into()))
+        );
+        assert_eq!(parse_proto_str(&model_to_proto(&model)).unwrap(), model);
+    }
+
+    #[test]
+    fn lint_enum_naming_flags_non_upper_snake_values() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            enum Status {
+                STATUS_UNKNOWN = 0;
+                paid = 1;
+                HTTP_2 = 2;
+                Refunded = 3;
+            }
+        "#,
+        )
+        .unwrap();
+        // style only: the model still validates
+        assert_eq!(validate(&model), Ok(()));
+        assert_eq!(
+            lint_enum_naming(&model),
+            vec![
+                ValidationError::EnumNamingViolation {
+                    enum_name: "Status".into(),
+                    value: "paid".into()
+                },
+                ValidationError::EnumNamingViolation {
+                    enum_name: "Status".into(),
+                    value: "Refunded".into()
+                },
+            ]
+        );
+        assert_eq!(
+            lint_enum_naming(&model)[0].to_string(),
+            "Status.paid: enum values should be UPPER_SNAKE_CASE"
+        );
+    }
+
+    #[test]
+    fn tabs_and_form_feeds_are_whitespace() {
+        let model = parse_proto_str(
+            "syntax = \"proto3\";\npackage\tshop;\n\nmessage Order {\n\tint32\tid = 1;\n\x0c\trepeated\tstring tags = 2;\n\x0b}\n",
+        )
+        .unwrap();
+        assert_eq!(model.package.as_deref(), Some("shop"));
+        let order = get_message(&model, "Order");
+        let fields: Vec<(&str, u32)> = order
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.order))
+            .collect();
+        assert_eq!(fields, [("id", 1), ("tags", 2)]);
+        assert_eq!(order.fields[1].label, Some(FieldLabel::Repeated));
+    }
+
+    #[test]
+    fn assignments_without_spaces_parse() {
+        let model = parse_proto_str(
+            r#"syntax="proto3";
+            message Order{int64 id=1;string note=2[json_name="n"];Status status=3;}
+            enum Status{UNKNOWN=0;PAID=1;}"#,
+        )
+        .unwrap();
+        let order = get_message(&model, "Order");
+        assert_eq!(order.fields[0].name, "id");
+        assert_eq!(order.fields[0].ty, FieldType::Scalar(ScalarType::Int64));
+        assert_eq!(order.fields[1].order, 2);
+        assert_eq!(order.fields[1].json_name.as_deref(), Some("n"));
+        assert_eq!(order.fields[2].ty, FieldType::Custom("Status".into()));
+        let status = get_enum(&model, "Status");
+        assert_eq!(status.values[1].name, "PAID");
+        assert_eq!(status.values[1].number, 1);
+    }
+
+    #[test]
+    fn field_resolves_nested_enum_declared_after_it() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+                Status status = 1;
+                repeated Line lines = 2;
+                oneof payment { Method method = 3; }
+                enum Status { NEW = 0; PAID = 1; }
+                message Line { Unit unit = 1; enum Unit { PIECE = 0; } }
+                enum Method { CARD = 0; }
+            }
+        "#,
+        )
+        .unwrap();
+        let order = get_message(&model, "Order");
+        let types: Vec<&FieldType> = order.fields.iter().map(|f| &f.ty).collect();
+        assert_eq!(
+            types,
+            [
+                &FieldType::Custom("Order.Status".into()),
+                &FieldType::Custom("Order.Line".into()),
+                &FieldType::Custom("Order.Method".into()),
+            ]
+        );
+        assert_eq!(
+            get_message(&model, "Order.Line").fields[0].ty,
+            FieldType::Custom("Order.Line.Unit".into())
+        );
+        assert_eq!(get_enum(&model, "Order.Status").values[1].name, "PAID");
+    }
+
+    #[test]
+    fn parse_errors_name_the_file() {
+        let dir = Path::new("target/tmp");
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join("broken.proto");
+        fs::write(
+            &path,
+            "syntax = \"proto3\";\nmessage Broken { int32 id = ; }\n",
+        )
+        .unwrap();
+
+        let err = parse_proto_file(&path).unwrap_err();
+        let shown = err.to_string();
+        assert!(
+            shown.starts_with(&format!("{}: Parse error:", path.display())),
+            "{}",
+            shown
+        );
+
+        // wrapping twice keeps the original file
+        let err = err.with_file("other.proto");
+        assert!(err.to_string().contains("broken.proto"));
+        assert!(!err.to_string().contains("other.proto"));
+    }
+
+    #[test]
+    fn referrers_find_messages_using_a_type() {
+        let model = parse_proto_file("tests/resources/order.proto").expect("parse failed");
+        let names = |type_name: &str| -> Vec<String> {
+            model
+                .referrers(type_name)
+                .iter()
+                .map(|m| m.name.clone())
+                .collect()
+        };
+        assert_eq!(names("Order.Address"), ["Order"]);
+        assert_eq!(names("OrderItem"), ["Order"]);
+        assert_eq!(names("OrderItemType"), ["OrderItem"]);
+        assert!(names("Order").is_empty());
+    }
+
+    #[test]
+    fn edition_features_are_captured() {
+        let model = parse_proto_str(
+            r#"
+            edition = "2023";
+            package shop;
+            option features.field_presence = IMPLICIT;
+            option java_package = "com.shop";
+
+            message Order {
+                option features.field_presence = EXPLICIT;
+                int64 id = 1;
+                string note = 2 [features.field_presence = LEGACY_REQUIRED, deprecated = true];
+            }
+        "#,
+        )
+        .unwrap();
+        assert_eq!(model.edition.as_deref(), Some("2023"));
+        assert_eq!(model.syntax, ProtoSyntax::Proto3);
+        let presence =
+            |features: &HashMap<String, OptionValue>| features.get("field_presence").cloned();
+        assert_eq!(
+            presence(&model.features),
+            Some(OptionValue::Ident("IMPLICIT".into()))
+        );
+        assert_eq!(model.features.len(), 1);
+
+        let order = get_message(&model, "Order");
+        assert_eq!(
+            presence(&order.features),
+            Some(OptionValue::Ident("EXPLICIT".into()))
+        );
+        assert!(order.fields[0].features.is_empty());
+        assert_eq!(
+            presence(&order.fields[1].features),
+            Some(OptionValue::Ident("LEGACY_REQUIRED".into()))
+        );
+
+        let reparsed = parse_proto_str(&model_to_proto(&model)).expect("printed proto reparses");
+        assert_eq!(reparsed, model);
+    }
+
+    #[test]
+    fn nesting_beyond_limit_is_an_error() {
+        let content = r#"
+            syntax = "proto3";
+            message A { message B { message C { message D { int32 x = 1; } } } }
+        "#;
+        let options = ParseOptions {
+            max_nesting_depth: 3,
+            ..Default::default()
+        };
+        let err = parse_proto_str_with_options(content, &options).unwrap_err();
+        assert!(matches!(
+            &err,
+            ParseError::NestingTooDeep { message, limit: 3 } if message == "A.B.C.D"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "A.B.C.D: messages are nested more than 3 levels deep"
+        );
+
+        let options = ParseOptions {
+            max_nesting_depth: 4,
+            ..Default::default()
+        };
+        assert!(parse_proto_str_with_options(content, &options).is_ok());
+        assert!(parse_proto_str(content).is_ok());
+    }
+
+    #[test]
+    fn block_comments_hide_code_like_tokens() {
+        let model = parse_proto_str(
+            r#"
+            syntax = "proto3";
+            /* removed in v2:
+            message Legacy {
+                string name = 1; // "quoted" ; { }
+                enum Kind { A = 0; }
+            }
+            */
+            message Order {
+                int32 id = 1; /* was: string id = 1; } */
+                /* message Inner { } ; /* not nested */
+                string name = 2;
+            }
+            enum Status {
+                /* Was /* STATUS_NEW; see message Order { }. */
+                NEW = 0;
+            }
+        "#,
+        )
+        .unwrap();
+        let names: Vec<&str> = model.types.iter().map(|t| t.name()).collect();
+        assert_eq!(names, ["Order", "Status"]);
+        let order = get_message(&model, "Order");
+        let fields: Vec<&str> = order.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(fields, ["id", "name"]);
+        assert_eq!(
+            order.fields[0].trailing_doc.as_deref(),
+            Some("was: string id = 1; }")
+        );
+        assert_eq!(
+            get_enum(&model, "Status").values[0].leading_doc.as_deref(),
+            Some("Was /* STATUS_NEW; see message Order { }.")
+        );
     }
 }
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::model::{ImportKind, ProtoModel};
+use crate::{ParseError, parse_proto_str};
+
+// Multi-file loading: follow import statements from an entry file.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoFile {
+    pub path: String, // import path relative to the root, '/'-separated
+    pub model: ProtoModel,
+}
+
+/// Parse `entry` (relative to `root`) and, transitively, every file it imports.
+/// Import paths are resolved against `root`. The entry file comes first, each
+/// file appears once. `weak` imports that do not exist are skipped.
+pub fn load_proto_tree<P: AsRef<Path>>(root: P, entry: &str) -> Result<Vec<ProtoFile>, ParseError> {
+    let root = root.as_ref();
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![(entry.to_string(), ImportKind::Normal)];
+    while let Some((path, kind)) = pending.pop() {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(root.join(&path))
+            .map_err(ParseError::Io)
+            .and_then(|content| parse_proto_str(&content));
+        let model = match parsed {
+            Ok(model) => model,
+            Err(ParseError::Io(e))
+                if kind == ImportKind::Weak && e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                continue;
+            }
+            Err(e) => return Err(e.with_file(root.join(&path))),
+        };
+        // Push in reverse so imports are visited in declaration order
+        for import in model.imports.iter().rev() {
+            pending.push((import.path.clone(), import.kind));
+        }
+        files.push(ProtoFile { path, model });
+    }
+    Ok(files)
+}
+
+/// The files whose types `path` can reference: itself, its direct imports,
+/// and whatever those re-export through `import public`, transitively.
+pub fn visible_files<'a>(files: &'a [ProtoFile], path: &str) -> Vec<&'a ProtoFile> {
+    let find = |p: &str| files.iter().find(|f| f.path == p);
+    let Some(file) = find(path) else {
+        return Vec::new();
+    };
+    let mut visible = vec![file];
+    let mut pending: Vec<&str> = file.model.imports.iter().map(|i| i.path.as_str()).collect();
+    while let Some(p) = pending.pop() {
+        let Some(imported) = find(p) else { continue };
+        if visible.iter().any(|v| v.path == imported.path) {
+            continue;
+        }
+        visible.push(imported);
+        pending.extend(
+            imported
+                .model
+                .imports
+                .iter()
+                .filter(|i| i.kind == ImportKind::Public)
+                .map(|i| i.path.as_str()),
+        );
+    }
+    visible
+}
@@ -1,45 +1,277 @@
 #![allow(dead_code)]
 
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::RangeInclusive;
+
 // Minimal IR for .proto files per current requirements.
-// - Top-level has package and a list of types (message or enum only).
+// - Top-level has package, a list of types (message or enum) and services.
 // - Message contains only fields.
-// - Field type is either a scalar or a custom type.
-// - Field has a name and order (tag).
+// - Field type is a scalar, a type reference or a map of those.
+// - Field has a name, order (tag) and an optional label.
 
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProtoModel {
+    pub syntax: ProtoSyntax,
     pub package: Option<String>,
+    pub imports: Vec<Import>,
     pub types: Vec<TypeDecl>,
+    pub services: Vec<Service>,
+    pub extensions: Vec<Extension>, // proto2 `extend Foo { ... }` blocks
+    pub file_comment: Option<String>, // comments before the first statement, e.g. a license
+    pub edition: Option<String>,    // `edition = "2023";` instead of a syntax statement
+    pub features: HashMap<String, OptionValue>, // file-level `option features.<name> = ...;`
+}
+
+// Parsed files without a `syntax` statement are Proto2, as with protoc; the
+// Default here only applies to models built in code. Edition files are Proto3
+// (no labels needed), with presence and the like given by `features`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProtoSyntax {
+    Proto2,
+    #[default]
+    Proto3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    pub path: String, // file path as written in the import statement
+    pub kind: ImportKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImportKind {
+    Normal,
+    Public,
+    Weak,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeDecl {
     Message(Message),
     Enum(Enum),
 }
 
+impl TypeDecl {
+    // Declared name; nested types are dotted (`Order.Address`)
+    pub fn name(&self) -> &str {
+        match self {
+            TypeDecl::Message(m) => &m.name,
+            TypeDecl::Enum(e) => &e.name,
+        }
+    }
+}
+
 // ---------------- Message & Fields ----------------
 
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message {
     pub name: String,
     pub fields: Vec<Field>,
+    pub options: Vec<(String, OptionValue)>, // `option ... ;` entries in source order
+    pub features: HashMap<String, OptionValue>, // `features.*` options, by feature name
+    pub extension_ranges: Vec<ReservedRange>, // proto2 `extensions 100 to 199;`
+}
+
+// Field numbers set aside by a message statement; `max` is 536870911, the
+// largest field number.
+pub type ReservedRange = RangeInclusive<u32>;
+
+impl Message {
+    // Value of the message option `name`, if set
+    pub fn option(&self, name: &str) -> Option<&OptionValue> {
+        self.options.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.option("deprecated") == Some(&OptionValue::Bool(true))
+    }
+
+    // Synthetic `*Entry` message protoc generates for a map field
+    pub fn is_map_entry(&self) -> bool {
+        self.option("map_entry") == Some(&OptionValue::Bool(true))
+    }
+
+    // Whether `number` is left to extensions and so unavailable to fields
+    pub fn is_extension_number(&self, number: u32) -> bool {
+        self.extension_ranges.iter().any(|r| r.contains(&number))
+    }
+
+    pub fn field_by_tag(&self, tag: u32) -> Option<&Field> {
+        self.fields.iter().find(|f| f.order == tag)
+    }
+
+    pub fn field_by_name(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    // Custom types referenced by the fields, deduplicated in first-use order.
+    // Nested types are qualified (`Order.Address`); others are as written.
+    pub fn dependencies(&self) -> Vec<String> {
+        let mut deps: Vec<String> = Vec::new();
+        for f in &self.fields {
+            if let Some(name) = f.ty.type_name()
+                && !deps.iter().any(|d| d == name)
+            {
+                deps.push(name.to_string());
+            }
+        }
+        deps
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
     pub ty: FieldType,
     pub name: String,
-    pub order: u32, // tag number
+    pub order: u32,                             // tag number
+    pub label: Option<FieldLabel>,              // None when no modifier is written
+    pub trailing_doc: Option<String>,           // `// ...` comment on the same line
+    pub options: Vec<(String, OptionValue)>,    // `[name = value, ...]` in source order
+    pub json_name: Option<String>,              // `[json_name = "..."]` override
+    pub oneof: Option<String>,                  // name of the enclosing oneof, if any
+    pub features: HashMap<String, OptionValue>, // `features.*` options, by feature name
+}
+
+impl Field {
+    // Value of the option `name`, if set
+    pub fn option(&self, name: &str) -> Option<&OptionValue> {
+        self.options.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    // proto2 `[default = ...]` value, if any
+    pub fn default_value(&self) -> Option<&OptionValue> {
+        self.option("default")
+    }
+
+    // `[ctype = CORD]`; unknown values are None
+    pub fn ctype(&self) -> Option<CType> {
+        match self.option("ctype")? {
+            OptionValue::Ident(v) if v == "STRING" => Some(CType::String),
+            OptionValue::Ident(v) if v == "CORD" => Some(CType::Cord),
+            OptionValue::Ident(v) if v == "STRING_PIECE" => Some(CType::StringPiece),
+            _ => None,
+        }
+    }
+
+    // `[jstype = JS_STRING]`; unknown values are None
+    pub fn jstype(&self) -> Option<JsType> {
+        match self.option("jstype")? {
+            OptionValue::Ident(v) if v == "JS_NORMAL" => Some(JsType::Normal),
+            OptionValue::Ident(v) if v == "JS_STRING" => Some(JsType::String),
+            OptionValue::Ident(v) if v == "JS_NUMBER" => Some(JsType::Number),
+            _ => None,
+        }
+    }
+
+    // `[deprecated = true]`
+    pub fn is_deprecated(&self) -> bool {
+        self.option("deprecated") == Some(&OptionValue::Bool(true))
+    }
+
+    // `[lazy = true]`
+    pub fn is_lazy(&self) -> bool {
+        self.option("lazy") == Some(&OptionValue::Bool(true))
+    }
+
+    // Key in the proto3 JSON mapping: `[json_name]` when given, otherwise
+    // protoc's default (`_` dropped, the following letter upper-cased)
+    pub fn json_key(&self) -> String {
+        if let Some(name) = &self.json_name {
+            return name.clone();
+        }
+        let mut key = String::with_capacity(self.name.len());
+        let mut upper = false;
+        for c in self.name.chars() {
+            if c == '_' {
+                upper = true;
+            } else if upper {
+                key.extend(c.to_uppercase());
+                upper = false;
+            } else {
+                key.push(c);
+            }
+        }
+        key
+    }
+}
+
+// C++ representation of a string/bytes field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CType {
+    String,
+    Cord,
+    StringPiece,
+}
+
+// JavaScript representation of a 64-bit integer field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsType {
+    Normal,
+    String,
+    Number,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldLabel {
+    Optional,
+    Required,
+    Repeated,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FieldType {
     Scalar(ScalarType),
     Custom(String), // fully-qualified or simple type name
+    // set by the ResolveFieldTypes pass: a type declared in this file, by its
+    // declared name (`Order.Status`)
+    Message(String),
+    Enum(String),
+    // `map<string, Order.Status>`; keys are integral, bool or string scalars
+    Map {
+        key: ScalarType,
+        value: Box<FieldType>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl FieldType {
+    // Referenced type name, resolved or not (a map's value type); None for scalars
+    pub fn type_name(&self) -> Option<&str> {
+        match self {
+            FieldType::Scalar(_) => None,
+            FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => {
+                Some(name)
+            }
+            FieldType::Map { value, .. } => value.type_name(),
+        }
+    }
+
+    // Type of a single value: the value type for maps, otherwise itself
+    pub fn value_type(&self) -> &FieldType {
+        match self {
+            FieldType::Map { value, .. } => value,
+            other => other,
+        }
+    }
+
+    pub fn value_type_mut(&mut self) -> &mut FieldType {
+        match self {
+            FieldType::Map { value, .. } => value,
+            other => other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScalarType {
     Double,
     Float,
@@ -58,16 +290,273 @@ pub enum ScalarType {
     Bytes,
 }
 
+impl ScalarType {
+    // Whether a field can change between the two types without breaking
+    // parsing of existing data, per protobuf's wire-compatibility rules.
+    pub fn wire_compatible_with(&self, other: &ScalarType) -> bool {
+        use ScalarType::*;
+        let group = |t: &ScalarType| match t {
+            Int32 | Int64 | Uint32 | Uint64 | Bool => 0, // varint (enums too)
+            Sint32 | Sint64 => 1,                        // zigzag varint
+            Fixed32 | Sfixed32 => 2,
+            Fixed64 | Sfixed64 => 3,
+            String | Bytes => 4, // as long as the bytes are valid UTF-8
+            Float => 5,
+            Double => 6,
+        };
+        group(self) == group(other)
+    }
+}
+
+// Constant value of an option, e.g. `[default = RED]` is Ident("RED").
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OptionValue {
+    Ident(String), // enum constant or other bare identifier
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Aggregate(String), // raw body of a `{ ... }` message literal, braces stripped
+}
+
 // ---------------- Enum ----------------
 
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Enum {
     pub name: String,
     pub values: Vec<EnumValue>,
+    pub reserved_numbers: Vec<RangeInclusive<i32>>, // `reserved 2, 9 to 11;`, `max` is i32::MAX
+    pub reserved_names: Vec<String>,                // `reserved "FOO", "BAR";`
+}
+
+impl Enum {
+    pub fn is_reserved_number(&self, number: i32) -> bool {
+        self.reserved_numbers.iter().any(|r| r.contains(&number))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumValue {
     pub name: String,
     pub number: i32,
+    pub trailing_doc: Option<String>, // `// ...` comment on the same line
+    pub leading_doc: Option<String>,  // comment lines directly above, joined by '\n'
+    pub options: Vec<(String, OptionValue)>, // `[deprecated = true, ...]` in source order
+}
+
+impl EnumValue {
+    pub fn is_deprecated(&self) -> bool {
+        self.options
+            .iter()
+            .any(|(n, v)| n == "deprecated" && *v == OptionValue::Bool(true))
+    }
+}
+
+// ---------------- Extension ----------------
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extension {
+    pub extendee: String, // extended message as written, leading dot stripped
+    pub fields: Vec<Field>,
+}
+
+// ---------------- Service ----------------
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Service {
+    pub name: String,
+    pub methods: Vec<RpcMethod>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RpcMethod {
+    pub name: String,
+    pub input_type: String,  // type name as written, leading dot stripped
+    pub output_type: String, // type name as written, leading dot stripped
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub options: Vec<(String, OptionValue)>, // `option ... ;` entries in the rpc body
+}
+
+// ---------------- Type resolution ----------------
+
+impl ProtoModel {
+    // Names of all declared messages and enums, nested ones dotted
+    // (`Order.Address`), relative to the file's package.
+    pub fn declared_type_names(&self) -> HashSet<String> {
+        self.types.iter().map(|t| t.name().to_string()).collect()
+    }
+
+    // Resolve a type reference the way protoc does: innermost scope first,
+    // then outwards. A reference may also carry the file's package prefix.
+    // Returns the declared name (package-relative), if the type is local.
+    pub fn resolve_type_name(&self, scope: &str, name: &str) -> Option<String> {
+        let is_declared = |n: &str| self.types.iter().any(|t| t.name() == n);
+        let name = match &self.package {
+            Some(pkg) => name
+                .strip_prefix(pkg.as_str())
+                .and_then(|rest| rest.strip_prefix('.'))
+                .unwrap_or(name),
+            None => name,
+        };
+
+        let mut scope = Some(scope).filter(|s| !s.is_empty());
+        while let Some(s) = scope {
+            let candidate = format!("{}.{}", s, name);
+            if is_declared(&candidate) {
+                return Some(candidate);
+            }
+            scope = s.rfind('.').map(|i| &s[..i]);
+        }
+        is_declared(name).then(|| name.to_string())
+    }
+
+    // Messages with a field (singular, repeated, map value or in a oneof) of
+    // the declared type `type_name` (`Order.Address`), in declaration order.
+    pub fn referrers(&self, type_name: &str) -> Vec<&Message> {
+        self.types
+            .iter()
+            .filter_map(|t| match t {
+                TypeDecl::Message(m) => Some(m),
+                TypeDecl::Enum(_) => None,
+            })
+            .filter(|m| {
+                m.fields.iter().any(|f| {
+                    f.ty.type_name().is_some_and(|name| {
+                        self.resolve_type_name(&m.name, name).as_deref() == Some(type_name)
+                    })
+                })
+            })
+            .collect()
+    }
+
+    // Types declared directly inside `parent`, in declaration order.
+    pub fn nested_types<'a>(&'a self, parent: &str) -> impl Iterator<Item = &'a TypeDecl> {
+        let prefix = format!("{}.", parent);
+        self.types.iter().filter(move |t| {
+            t.name()
+                .strip_prefix(&prefix)
+                .is_some_and(|rest| !rest.contains('.'))
+        })
+    }
+}
+
+// ---------------- Summary ----------------
+
+// Quick counts over a model, for introspection and tests.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModelSummary {
+    pub messages: usize,
+    pub enums: usize,
+    pub fields: usize,
+    pub nested_types: usize, // messages/enums declared inside a message
+    pub scalar_types: BTreeSet<ScalarType>,
+}
+
+impl ProtoModel {
+    pub fn summary(&self) -> ModelSummary {
+        let mut summary = ModelSummary::default();
+        for t in &self.types {
+            let name = match t {
+                TypeDecl::Message(m) => {
+                    summary.messages += 1;
+                    summary.fields += m.fields.len();
+                    for f in &m.fields {
+                        if let FieldType::Scalar(st) = f.ty.value_type() {
+                            summary.scalar_types.insert(st.clone());
+                        }
+                    }
+                    &m.name
+                }
+                TypeDecl::Enum(e) => {
+                    summary.enums += 1;
+                    &e.name
+                }
+            };
+            if name.contains('.') {
+                summary.nested_types += 1;
+            }
+        }
+        summary
+    }
+}
+
+// ---------------- Debug tree ----------------
+
+impl ProtoModel {
+    // Indented outline of the model for debugging: the package, then each
+    // top-level type with its fields (label, type, tag) or enum values, and
+    // nested types under their parent. Easier to scan than `{:#?}`.
+    pub fn debug_tree(&self) -> String {
+        let syntax = match (&self.edition, self.syntax) {
+            (Some(edition), _) => format!("edition {}", edition),
+            (None, ProtoSyntax::Proto2) => "proto2".to_string(),
+            (None, ProtoSyntax::Proto3) => "proto3".to_string(),
+        };
+        let mut out = format!(
+            "package {} ({})\n",
+            self.package.as_deref().unwrap_or("<none>"),
+            syntax
+        );
+        for t in self.types.iter().filter(|t| !t.name().contains('.')) {
+            self.debug_type(t, 1, &mut out);
+        }
+        out
+    }
+
+    fn debug_type(&self, t: &TypeDecl, depth: usize, out: &mut String) {
+        let pad = "  ".repeat(depth);
+        let simple = t.name().rsplit('.').next().unwrap_or(t.name());
+        match t {
+            TypeDecl::Message(m) => {
+                out.push_str(&format!("{}message {}\n", pad, simple));
+                for f in &m.fields {
+                    let label = match f.label {
+                        Some(FieldLabel::Optional) => "optional ",
+                        Some(FieldLabel::Required) => "required ",
+                        Some(FieldLabel::Repeated) => "repeated ",
+                        None => "",
+                    };
+                    let ty = debug_type_name(&f.ty);
+                    out.push_str(&format!(
+                        "{}  {}: {}{} = {}",
+                        pad, f.name, label, ty, f.order
+                    ));
+                    if let Some(oneof) = &f.oneof {
+                        out.push_str(&format!(" (oneof {})", oneof));
+                    }
+                    out.push('\n');
+                }
+                for nested in self.nested_types(&m.name) {
+                    self.debug_type(nested, depth + 1, out);
+                }
+            }
+            TypeDecl::Enum(e) => {
+                out.push_str(&format!("{}enum {}\n", pad, simple));
+                for v in &e.values {
+                    out.push_str(&format!("{}  {} = {}\n", pad, v.name, v.number));
+                }
+            }
+        }
+    }
+}
+
+fn debug_type_name(ty: &FieldType) -> String {
+    match ty {
+        FieldType::Scalar(st) => crate::printer::scalar_name(st).to_string(),
+        FieldType::Custom(name) => name.clone(),
+        FieldType::Message(name) => format!("message {}", name),
+        FieldType::Enum(name) => format!("enum {}", name),
+        FieldType::Map { key, value } => format!(
+            "map<{}, {}>",
+            crate::printer::scalar_name(key),
+            debug_type_name(value)
+        ),
+    }
 }
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use crate::model::*;
+
+// Reusable model transformations, run in order by a PassPipeline before
+// handing the model to a generator.
+
+pub trait ModelPass {
+    fn apply(&self, model: &mut ProtoModel);
+}
+
+#[derive(Default)]
+pub struct PassPipeline {
+    passes: Vec<Box<dyn ModelPass>>,
+}
+
+impl PassPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<P: ModelPass + 'static>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn run(&self, model: &mut ProtoModel) {
+        for pass in &self.passes {
+            pass.apply(model);
+        }
+    }
+}
+
+impl ModelPass for PassPipeline {
+    fn apply(&self, model: &mut ProtoModel) {
+        self.run(model);
+    }
+}
+
+// Drop fields and enum values marked `[deprecated = true]`.
+pub struct StripDeprecated;
+
+impl ModelPass for StripDeprecated {
+    fn apply(&self, model: &mut ProtoModel) {
+        for t in &mut model.types {
+            match t {
+                TypeDecl::Message(m) => m.fields.retain(|f| !f.is_deprecated()),
+                TypeDecl::Enum(e) => e.values.retain(|v| !v.is_deprecated()),
+            }
+        }
+    }
+}
+
+// Move the file from package `from` (or a sub-package of it) to `to`,
+// rewriting fully-qualified references into the renamed package.
+pub struct RenamePackage {
+    pub from: String,
+    pub to: String,
+}
+
+impl RenamePackage {
+    fn rename(&self, name: &str) -> Option<String> {
+        let rest = name.strip_prefix(self.from.as_str())?;
+        (rest.is_empty() || rest.starts_with('.')).then(|| format!("{}{}", self.to, rest))
+    }
+
+    fn rename_ref(&self, name: &mut String) {
+        if let Some(renamed) = self.rename(name) {
+            *name = renamed;
+        }
+    }
+
+    fn rename_field(&self, f: &mut Field) {
+        if let FieldType::Custom(name) = f.ty.value_type_mut() {
+            self.rename_ref(name);
+        }
+    }
+}
+
+impl ModelPass for RenamePackage {
+    fn apply(&self, model: &mut ProtoModel) {
+        if let Some(pkg) = &mut model.package {
+            self.rename_ref(pkg);
+        }
+        for t in &mut model.types {
+            if let TypeDecl::Message(m) = t {
+                m.fields.iter_mut().for_each(|f| self.rename_field(f));
+            }
+        }
+        for ext in &mut model.extensions {
+            self.rename_ref(&mut ext.extendee);
+            ext.fields.iter_mut().for_each(|f| self.rename_field(f));
+        }
+        for m in model.services.iter_mut().flat_map(|s| &mut s.methods) {
+            self.rename_ref(&mut m.input_type);
+            self.rename_ref(&mut m.output_type);
+        }
+    }
+}
+
+// Turn `Custom` references to types declared in this file into
+// `FieldType::Message` or `FieldType::Enum` carrying the declared name.
+// References that don't resolve here (other files) stay `Custom`.
+pub struct ResolveFieldTypes;
+
+impl ModelPass for ResolveFieldTypes {
+    fn apply(&self, model: &mut ProtoModel) {
+        let enums: HashSet<&str> = model
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeDecl::Enum(e) => Some(e.name.as_str()),
+                TypeDecl::Message(_) => None,
+            })
+            .collect();
+        let resolve = |scope: &str, fields: &[Field]| -> Vec<Option<FieldType>> {
+            fields
+                .iter()
+                .map(|f| {
+                    let FieldType::Custom(name) = f.ty.value_type() else {
+                        return None;
+                    };
+                    let declared = model.resolve_type_name(scope, name)?;
+                    let resolved = if enums.contains(declared.as_str()) {
+                        FieldType::Enum(declared)
+                    } else {
+                        FieldType::Message(declared)
+                    };
+                    Some(match &f.ty {
+                        FieldType::Map { key, .. } => FieldType::Map {
+                            key: key.clone(),
+                            value: Box::new(resolved),
+                        },
+                        _ => resolved,
+                    })
+                })
+                .collect()
+        };
+        // one entry per message, then per extension block (file scope)
+        let resolved: Vec<_> = model
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeDecl::Message(m) => Some(resolve(&m.name, &m.fields)),
+                TypeDecl::Enum(_) => None,
+            })
+            .chain(model.extensions.iter().map(|ext| resolve("", &ext.fields)))
+            .collect();
+
+        let fields = model
+            .types
+            .iter_mut()
+            .filter_map(|t| match t {
+                TypeDecl::Message(m) => Some(&mut m.fields),
+                TypeDecl::Enum(_) => None,
+            })
+            .chain(model.extensions.iter_mut().map(|ext| &mut ext.fields));
+        for (fields, resolved) in fields.zip(resolved) {
+            for (f, ty) in fields.iter_mut().zip(resolved) {
+                if let Some(ty) = ty {
+                    f.ty = ty;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::model::*;
+use crate::validate::MAX_TAG;
+
+// Render a model back to .proto source. Nested types are re-nested under
+// their parent message. The file comment and enum value docs are printed as
+// `//` lines; other comments besides trailing docs are not preserved.
+pub fn model_to_proto(model: &ProtoModel) -> String {
+    let mut out = String::new();
+    if let Some(comment) = &model.file_comment {
+        for line in comment.lines() {
+            out.push_str(format!("// {}", line).trim_end());
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    if let Some(edition) = &model.edition {
+        out.push_str(&format!("edition = \"{}\";\n", escape(edition)));
+    } else {
+        let syntax = match model.syntax {
+            ProtoSyntax::Proto2 => "proto2",
+            ProtoSyntax::Proto3 => "proto3",
+        };
+        out.push_str(&format!("syntax = \"{}\";\n", syntax));
+    }
+    if let Some(pkg) = &model.package {
+        out.push_str(&format!("\npackage {};\n", pkg));
+    }
+    if !model.features.is_empty() {
+        out.push('\n');
+        let mut features: Vec<_> = model.features.iter().collect();
+        features.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in features {
+            out.push_str(&format!(
+                "option features.{} = {};\n",
+                name,
+                option_value(value)
+            ));
+        }
+    }
+    if !model.imports.is_empty() {
+        out.push('\n');
+        for i in &model.imports {
+            let kind = match i.kind {
+                ImportKind::Normal => "",
+                ImportKind::Public => "public ",
+                ImportKind::Weak => "weak ",
+            };
+            out.push_str(&format!("import {}\"{}\";\n", kind, escape(&i.path)));
+        }
+    }
+
+    for t in model.types.iter().filter(|t| !t.name().contains('.')) {
+        out.push('\n');
+        print_type(model, t, 0, &mut out);
+    }
+    for ext in &model.extensions {
+        out.push_str(&format!("\nextend {} {{\n", ext.extendee));
+        print_fields(&ext.fields, 1, &mut out);
+        out.push_str("}\n");
+    }
+    for svc in &model.services {
+        out.push_str(&format!("\nservice {} {{\n", svc.name));
+        for m in &svc.methods {
+            let stream = |s: bool| if s { "stream " } else { "" };
+            out.push_str(&format!(
+                "  rpc {} ({}{}) returns ({}{})",
+                m.name,
+                stream(m.client_streaming),
+                m.input_type,
+                stream(m.server_streaming),
+                m.output_type
+            ));
+            if m.options.is_empty() {
+                out.push_str(";\n");
+            } else {
+                out.push_str(" {\n");
+                for (name, value) in &m.options {
+                    out.push_str(&format!("    option {} = {};\n", name, option_value(value)));
+                }
+                out.push_str("  }\n");
+            }
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn print_type(model: &ProtoModel, t: &TypeDecl, depth: usize, out: &mut String) {
+    let pad = "  ".repeat(depth);
+    let full = t.name();
+    let simple = full.rsplit('.').next().unwrap_or(full);
+    match t {
+        TypeDecl::Message(m) => {
+            out.push_str(&format!("{}message {} {{\n", pad, simple));
+            for (name, value) in &m.options {
+                out.push_str(&format!(
+                    "{}  option {} = {};\n",
+                    pad,
+                    name,
+                    option_value(value)
+                ));
+            }
+            print_fields(&m.fields, depth + 1, out);
+            if !m.extension_ranges.is_empty() {
+                let ranges: Vec<String> = m
+                    .extension_ranges
+                    .iter()
+                    .map(|r| match (*r.start(), *r.end()) {
+                        (from, to) if from == to => from.to_string(),
+                        (from, MAX_TAG) => format!("{} to max", from),
+                        (from, to) => format!("{} to {}", from, to),
+                    })
+                    .collect();
+                out.push_str(&format!("{}  extensions {};\n", pad, ranges.join(", ")));
+            }
+            for nested in model.nested_types(full) {
+                print_type(model, nested, depth + 1, out);
+            }
+            out.push_str(&format!("{}}}\n", pad));
+        }
+        TypeDecl::Enum(e) => {
+            out.push_str(&format!("{}enum {} {{\n", pad, simple));
+            if !e.reserved_numbers.is_empty() {
+                let ranges: Vec<String> = e
+                    .reserved_numbers
+                    .iter()
+                    .map(|r| match (*r.start(), *r.end()) {
+                        (from, to) if from == to => from.to_string(),
+                        (from, i32::MAX) => format!("{} to max", from),
+                        (from, to) => format!("{} to {}", from, to),
+                    })
+                    .collect();
+                out.push_str(&format!("{}  reserved {};\n", pad, ranges.join(", ")));
+            }
+            if !e.reserved_names.is_empty() {
+                let names: Vec<String> = e
+                    .reserved_names
+                    .iter()
+                    .map(|n| format!("\"{}\"", escape(n)))
+                    .collect();
+                out.push_str(&format!("{}  reserved {};\n", pad, names.join(", ")));
+            }
+            for v in &e.values {
+                if let Some(doc) = &v.leading_doc {
+                    for line in doc.lines() {
+                        let line = format!("// {}", line);
+                        out.push_str(&format!("{}  {}\n", pad, line.trim_end()));
+                    }
+                }
+                out.push_str(&format!("{}  {} = {}", pad, v.name, v.number));
+                push_options(&v.options, out);
+                out.push(';');
+                push_trailing_doc(v.trailing_doc.as_deref(), out);
+            }
+            out.push_str(&format!("{}}}\n", pad));
+        }
+    }
+}
+
+// Consecutive fields sharing a oneof name are grouped back into one block.
+fn print_fields(fields: &[Field], depth: usize, out: &mut String) {
+    let pad = "  ".repeat(depth);
+    let mut i = 0;
+    while i < fields.len() {
+        let Some(oneof) = &fields[i].oneof else {
+            print_field(&fields[i], &pad, out);
+            i += 1;
+            continue;
+        };
+        out.push_str(&format!("{}oneof {} {{\n", pad, oneof));
+        let inner = format!("{}  ", pad);
+        while i < fields.len() && fields[i].oneof.as_ref() == Some(oneof) {
+            print_field(&fields[i], &inner, out);
+            i += 1;
+        }
+        out.push_str(&format!("{}}}\n", pad));
+    }
+}
+
+fn print_field(f: &Field, pad: &str, out: &mut String) {
+    out.push_str(pad);
+    match f.label {
+        Some(FieldLabel::Optional) => out.push_str("optional "),
+        Some(FieldLabel::Required) => out.push_str("required "),
+        Some(FieldLabel::Repeated) => out.push_str("repeated "),
+        None => {}
+    }
+    out.push_str(&format!("{} {} = {}", type_text(&f.ty), f.name, f.order));
+    push_options(&f.options, out);
+    out.push(';');
+    push_trailing_doc(f.trailing_doc.as_deref(), out);
+}
+
+fn push_options(options: &[(String, OptionValue)], out: &mut String) {
+    if !options.is_empty() {
+        let opts: Vec<String> = options
+            .iter()
+            .map(|(name, value)| format!("{} = {}", name, option_value(value)))
+            .collect();
+        out.push_str(&format!(" [{}]", opts.join(", ")));
+    }
+}
+
+fn push_trailing_doc(doc: Option<&str>, out: &mut String) {
+    if let Some(doc) = doc {
+        out.push_str(&format!(" // {}", doc));
+    }
+    out.push('\n');
+}
+
+fn option_value(v: &OptionValue) -> String {
+    match v {
+        OptionValue::Ident(s) => s.clone(),
+        OptionValue::Int(n) => n.to_string(),
+        OptionValue::Float(x) if x.is_nan() => "nan".to_string(),
+        OptionValue::Float(x) if x.is_infinite() => {
+            if *x > 0.0 { "inf" } else { "-inf" }.to_string()
+        }
+        // `{:?}` keeps the decimal point (`1.0`) so it reparses as a float
+        OptionValue::Float(x) => format!("{:?}", x),
+        OptionValue::Bool(b) => b.to_string(),
+        OptionValue::Str(s) => format!("\"{}\"", escape(s)),
+        OptionValue::Aggregate(body) => format!("{{ {} }}", body),
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn type_text(ty: &FieldType) -> String {
+    match ty {
+        FieldType::Scalar(st) => scalar_name(st).to_string(),
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => name.clone(),
+        FieldType::Map { key, value } => format!("map<{}, {}>", scalar_name(key), type_text(value)),
+    }
+}
+
+pub(crate) fn scalar_name(st: &ScalarType) -> &'static str {
+    match st {
+        ScalarType::Double => "double",
+        ScalarType::Float => "float",
+        ScalarType::Int32 => "int32",
+        ScalarType::Int64 => "int64",
+        ScalarType::Uint32 => "uint32",
+        ScalarType::Uint64 => "uint64",
+        ScalarType::Sint32 => "sint32",
+        ScalarType::Sint64 => "sint64",
+        ScalarType::Fixed32 => "fixed32",
+        ScalarType::Fixed64 => "fixed64",
+        ScalarType::Sfixed32 => "sfixed32",
+        ScalarType::Sfixed64 => "sfixed64",
+        ScalarType::Bool => "bool",
+        ScalarType::String => "string",
+        ScalarType::Bytes => "bytes",
+    }
+}
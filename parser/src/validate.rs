@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::model::*;
+
+// Semantic checks over a parsed model. Parsing accepts anything the grammar
+// allows; validation reports what protoc would reject.
+
+// Tags must be in 1..=2^29-1 and outside the implementation-reserved range.
+pub(crate) const MAX_TAG: u32 = (1 << 29) - 1;
+const RESERVED_TAGS: std::ops::RangeInclusive<u32> = 19000..=19999;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    DuplicateTag {
+        message: String,
+        tag: u32,
+    },
+    InvalidTag {
+        message: String,
+        field: String,
+        tag: u32,
+    },
+    UnknownType {
+        message: String,
+        field: String,
+        type_name: String,
+    },
+    // proto3 enums must start with a zero value
+    EnumZeroMissing {
+        enum_name: String,
+    },
+    // proto2 fields outside a oneof need optional/required/repeated
+    MissingLabel {
+        message: String,
+        field: String,
+    },
+    RequiredInProto3 {
+        message: String,
+        field: String,
+    },
+    // enum value whose number or name is listed in a `reserved` statement
+    ReservedEnumNumber {
+        enum_name: String,
+        value: String,
+        number: i32,
+    },
+    ReservedEnumName {
+        enum_name: String,
+        value: String,
+    },
+    // field numbered inside one of the message's `extensions` ranges
+    FieldInExtensionRange {
+        message: String,
+        field: String,
+        tag: u32,
+    },
+    // style lint, only reported by `lint_enum_naming`
+    EnumNamingViolation {
+        enum_name: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::DuplicateTag { message, tag } => {
+                write!(f, "{}: tag {} is used more than once", message, tag)
+            }
+            ValidationError::InvalidTag {
+                message,
+                field,
+                tag,
+            } => write!(f, "{}.{}: invalid tag {}", message, field, tag),
+            ValidationError::UnknownType {
+                message,
+                field,
+                type_name,
+            } => write!(f, "{}.{}: unknown type `{}`", message, field, type_name),
+            ValidationError::EnumZeroMissing { enum_name } => {
+                write!(f, "{}: first enum value must be zero in proto3", enum_name)
+            }
+            ValidationError::MissingLabel { message, field } => write!(
+                f,
+                "{}.{}: proto2 fields need an optional, required or repeated label",
+                message, field
+            ),
+            ValidationError::RequiredInProto3 { message, field } => {
+                write!(
+                    f,
+                    "{}.{}: required fields are not allowed in proto3",
+                    message, field
+                )
+            }
+            ValidationError::ReservedEnumNumber {
+                enum_name,
+                value,
+                number,
+            } => write!(f, "{}.{}: number {} is reserved", enum_name, value, number),
+            ValidationError::ReservedEnumName { enum_name, value } => {
+                write!(f, "{}.{}: name is reserved", enum_name, value)
+            }
+            ValidationError::FieldInExtensionRange {
+                message,
+                field,
+                tag,
+            } => write!(
+                f,
+                "{}.{}: tag {} is in an extensions range",
+                message, field, tag
+            ),
+            ValidationError::EnumNamingViolation { enum_name, value } => {
+                write!(
+                    f,
+                    "{}.{}: enum values should be UPPER_SNAKE_CASE",
+                    enum_name, value
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Run all semantic checks over the model, returning every problem found.
+/// Types are resolved within the model only, so it should be self-contained.
+pub fn validate(model: &ProtoModel) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    for t in &model.types {
+        match t {
+            TypeDecl::Message(m) => validate_message(model, m, &mut errors),
+            TypeDecl::Enum(e) => validate_enum(model, e, &mut errors),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Opt-in style check: every enum value name must be UPPER_SNAKE_CASE.
+/// Not part of [`validate`]; protoc accepts any identifier.
+pub fn lint_enum_naming(model: &ProtoModel) -> Vec<ValidationError> {
+    model
+        .types
+        .iter()
+        .filter_map(|t| match t {
+            TypeDecl::Enum(e) => Some(e),
+            TypeDecl::Message(_) => None,
+        })
+        .flat_map(|e| {
+            e.values
+                .iter()
+                .filter(|v| !is_upper_snake_case(&v.name))
+                .map(|v| ValidationError::EnumNamingViolation {
+                    enum_name: e.name.clone(),
+                    value: v.name.clone(),
+                })
+        })
+        .collect()
+}
+
+// `PAID`, `PAID_IN_FULL`, `HTTP_2`; not `Paid`, `_PAID`, `PAID__FULL`
+fn is_upper_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.split('_').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        })
+}
+
+fn validate_enum(model: &ProtoModel, e: &Enum, errors: &mut Vec<ValidationError>) {
+    if model.syntax == ProtoSyntax::Proto3 && e.values.first().is_some_and(|v| v.number != 0) {
+        errors.push(ValidationError::EnumZeroMissing {
+            enum_name: e.name.clone(),
+        });
+    }
+    for v in &e.values {
+        if e.is_reserved_number(v.number) {
+            errors.push(ValidationError::ReservedEnumNumber {
+                enum_name: e.name.clone(),
+                value: v.name.clone(),
+                number: v.number,
+            });
+        }
+        if e.reserved_names.contains(&v.name) {
+            errors.push(ValidationError::ReservedEnumName {
+                enum_name: e.name.clone(),
+                value: v.name.clone(),
+            });
+        }
+    }
+}
+
+fn validate_message(model: &ProtoModel, m: &Message, errors: &mut Vec<ValidationError>) {
+    let mut seen: HashMap<u32, usize> = HashMap::new();
+    for f in &m.fields {
+        *seen.entry(f.order).or_default() += 1;
+
+        if f.order == 0 || f.order > MAX_TAG || RESERVED_TAGS.contains(&f.order) {
+            errors.push(ValidationError::InvalidTag {
+                message: m.name.clone(),
+                field: f.name.clone(),
+                tag: f.order,
+            });
+        }
+        if m.is_extension_number(f.order) {
+            errors.push(ValidationError::FieldInExtensionRange {
+                message: m.name.clone(),
+                field: f.name.clone(),
+                tag: f.order,
+            });
+        }
+
+        match (model.syntax, f.label) {
+            // oneof members and map fields take no label
+            (ProtoSyntax::Proto2, None)
+                if f.oneof.is_none() && !matches!(f.ty, FieldType::Map { .. }) =>
+            {
+                errors.push(ValidationError::MissingLabel {
+                    message: m.name.clone(),
+                    field: f.name.clone(),
+                });
+            }
+            (ProtoSyntax::Proto3, Some(FieldLabel::Required)) => {
+                errors.push(ValidationError::RequiredInProto3 {
+                    message: m.name.clone(),
+                    field: f.name.clone(),
+                });
+            }
+            _ => {}
+        }
+
+        if let FieldType::Custom(name) = f.ty.value_type()
+            && model.resolve_type_name(&m.name, name).is_none()
+        {
+            errors.push(ValidationError::UnknownType {
+                message: m.name.clone(),
+                field: f.name.clone(),
+                type_name: name.clone(),
+            });
+        }
+    }
+
+    let mut dups: Vec<u32> = seen
+        .into_iter()
+        .filter(|(_, n)| *n > 1)
+        .map(|(t, _)| t)
+        .collect();
+    dups.sort_unstable();
+    for tag in dups {
+        errors.push(ValidationError::DuplicateTag {
+            message: m.name.clone(),
+            tag,
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use crate::model::*;
+
+// Non-fatal findings: the model is usable, but something looks unintended.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseResult {
+    pub model: ProtoModel,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    // custom type name one typo away from a scalar, e.g. `int23`
+    MisspelledScalar {
+        message: String,
+        field: String,
+        type_name: String,
+        suggestion: &'static str,
+    },
+    // not declared here, and the file has no imports it could come from
+    UnresolvedType {
+        message: String,
+        field: String,
+        type_name: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::MisspelledScalar {
+                message,
+                field,
+                type_name,
+                suggestion,
+            } => write!(
+                f,
+                "{}.{}: type `{}` looks like a misspelling of `{}`",
+                message, field, type_name, suggestion
+            ),
+            Warning::UnresolvedType {
+                message,
+                field,
+                type_name,
+            } => write!(
+                f,
+                "{}.{}: type `{}` is not declared and nothing is imported",
+                message, field, type_name
+            ),
+        }
+    }
+}
+
+const SCALAR_NAMES: [&str; 15] = [
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+pub fn collect_warnings(model: &ProtoModel) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for t in &model.types {
+        let TypeDecl::Message(m) = t else { continue };
+        for f in &m.fields {
+            let FieldType::Custom(name) = f.ty.value_type() else {
+                continue;
+            };
+            if model.resolve_type_name(&m.name, name).is_some() {
+                continue;
+            }
+            if let Some(suggestion) = SCALAR_NAMES
+                .iter()
+                .find(|s| edit_distance(&name.to_ascii_lowercase(), s) <= 1)
+            {
+                warnings.push(Warning::MisspelledScalar {
+                    message: m.name.clone(),
+                    field: f.name.clone(),
+                    type_name: name.clone(),
+                    suggestion,
+                });
+            } else if model.imports.is_empty() {
+                warnings.push(Warning::UnresolvedType {
+                    message: m.name.clone(),
+                    field: f.name.clone(),
+                    type_name: name.clone(),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+// Edit distance counting an adjacent swap (`int23`) as one edit, over bytes;
+// names here are ASCII identifiers.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
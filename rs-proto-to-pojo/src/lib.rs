@@ -0,0 +1,23 @@
+//! Facade over the workspace crates. The parser is always available; each
+//! generator is behind a feature (`java` by default, `kotlin`,
+//! `json-mapping`, `openapi`, `rust`) so single-language users only compile
+//! what they use. The parsed model round-trips through JSON
+//! (`parser::model_to_json` / `parser::model_from_json`) unless the parser's
+//! default `serde` feature is turned off.
+
+pub use parser;
+
+#[cfg(feature = "java")]
+pub use java_generator as java;
+
+#[cfg(feature = "kotlin")]
+pub use kotlin_generator as kotlin;
+
+#[cfg(feature = "json-mapping")]
+pub use json_mapping_generator as json_mapping;
+
+#[cfg(feature = "openapi")]
+pub use openapi_generator as openapi;
+
+#[cfg(feature = "rust")]
+pub use rust_generator as rust;
@@ -0,0 +1,16 @@
+#![cfg(feature = "java")]
+
+use rs_proto_to_pojo::{java, parser};
+
+#[test]
+fn generates_java_through_facade() {
+    let model = parser::parse_proto_str(
+        "syntax = \"proto3\"; package demo; message Ping { int64 at = 1; }",
+    )
+    .expect("parse");
+    let files = java::generate_java_with_options(&model, &java::JavaGenOptions::default());
+    let (path, code) = &files[0];
+    assert_eq!(path, "demo/Ping.java");
+    assert!(code.contains("public class Ping {"));
+    assert!(code.contains("private long at;"));
+}
@@ -0,0 +1,638 @@
+use std::path::Path;
+
+use parser::{
+    Enum, Field, FieldLabel, FieldType, Message, ParseError, ProtoModel, ProtoSyntax, ScalarType,
+    TypeDecl, parse_proto_file,
+};
+
+/// A generated file: (relative_file_path, file_content).
+pub type GeneratedFile = (String, String);
+
+/// Generate prost-compatible Rust from a .proto file path.
+pub fn generate_rust_from_proto<P: AsRef<Path>>(
+    proto_path: P,
+) -> Result<Vec<GeneratedFile>, ParseError> {
+    let model = parse_proto_file(proto_path)?;
+    Ok(generate_rust_from_model(&model))
+}
+
+/// Generate one `<package>.rs` file (`_.rs` without a package) laid out the
+/// way prost-build does: `prost::Message` structs with `#[prost(...)]` field
+/// attributes, `prost::Enumeration` enums, a `prost::Oneof` enum per oneof,
+/// and nested types in a module named after their message in snake_case.
+pub fn generate_rust_from_model(model: &ProtoModel) -> Vec<GeneratedFile> {
+    let file = match &model.package {
+        Some(pkg) => format!("{}.rs", pkg),
+        None => "_.rs".to_string(),
+    };
+    let mut code = String::new();
+    render_items(model, None, &mut code);
+    vec![(file, code)]
+}
+
+// Messages and enums declared directly in `parent` (top level for None).
+fn render_items(model: &ProtoModel, parent: Option<&str>, out: &mut String) {
+    let items: Vec<&TypeDecl> = match parent {
+        None => model
+            .types
+            .iter()
+            .filter(|t| !t.name().contains('.'))
+            .collect(),
+        Some(p) => model.nested_types(p).collect(),
+    };
+    for (i, t) in items
+        .into_iter()
+        .filter(|t| !matches!(t, TypeDecl::Message(m) if m.is_map_entry()))
+        .enumerate()
+    {
+        if i > 0 {
+            out.push('\n');
+        }
+        match t {
+            TypeDecl::Message(m) => render_message(model, m, out),
+            TypeDecl::Enum(e) => render_enum(e, out),
+        }
+    }
+}
+
+fn render_message(model: &ProtoModel, m: &Message, out: &mut String) {
+    let pad = "    ".repeat(depth(&m.name));
+    let module = module_path(&m.name);
+    let inner = nested_module(&m.name);
+
+    out.push_str(&format!(
+        "{}#[derive(Clone, PartialEq, prost::Message)]\n",
+        pad
+    ));
+    out.push_str(&format!("{}pub struct {} {{\n", pad, simple_name(&m.name)));
+    let mut oneofs: Vec<(&str, Vec<&Field>)> = Vec::new();
+    for f in &m.fields {
+        match f.oneof.as_deref() {
+            None => {
+                let (attr, ty) = field_attr(model, m, f, &module);
+                out.push_str(&format!("{}    #[prost({})]\n", pad, attr));
+                out.push_str(&format!(
+                    "{}    pub {}: {},\n",
+                    pad,
+                    sanitize_ident(&f.name),
+                    ty
+                ));
+            }
+            Some(o) => match oneofs.iter_mut().find(|(name, _)| *name == o) {
+                Some((_, members)) => members.push(f),
+                None => oneofs.push((o, vec![f])),
+            },
+        }
+    }
+    // one property per oneof, after the plain fields as prost-build does
+    for (name, members) in &oneofs {
+        let tags: Vec<String> = members.iter().map(|f| f.order.to_string()).collect();
+        let path = format!("{}::{}", inner, sanitize_ident(&upper_camel(name)));
+        out.push_str(&format!(
+            "{}    #[prost(oneof = \"{}\", tags = \"{}\")]\n",
+            pad,
+            path,
+            tags.join(", ")
+        ));
+        out.push_str(&format!(
+            "{}    pub {}: Option<{}>,\n",
+            pad,
+            sanitize_ident(name),
+            path
+        ));
+    }
+    out.push_str(&format!("{}}}\n", pad));
+
+    let has_nested = model
+        .nested_types(&m.name)
+        .any(|t| !matches!(t, TypeDecl::Message(n) if n.is_map_entry()));
+    if oneofs.is_empty() && !has_nested {
+        return;
+    }
+    out.push_str(&format!(
+        "{}/// Nested message and enum types in `{}`.\n",
+        pad,
+        simple_name(&m.name)
+    ));
+    out.push_str(&format!("{}pub mod {} {{\n", pad, inner));
+    let mut inner_module = module.clone();
+    inner_module.push(inner.clone());
+    for (i, (name, members)) in oneofs.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{}    #[derive(Clone, PartialEq, prost::Oneof)]\n",
+            pad
+        ));
+        out.push_str(&format!(
+            "{}    pub enum {} {{\n",
+            pad,
+            sanitize_ident(&upper_camel(name))
+        ));
+        for f in members {
+            let (kind, ty) = field_kind(model, m, &f.ty, &inner_module);
+            let (kind, ty) = if needs_box(model, m, &f.ty) {
+                (format!("{}, boxed", kind), format!("Box<{}>", ty))
+            } else {
+                (kind, ty)
+            };
+            out.push_str(&format!(
+                "{}        #[prost({}, tag = \"{}\")]\n",
+                pad, kind, f.order
+            ));
+            out.push_str(&format!(
+                "{}        {}({}),\n",
+                pad,
+                sanitize_ident(&upper_camel(&f.name)),
+                ty
+            ));
+        }
+        out.push_str(&format!("{}    }}\n", pad));
+    }
+    if has_nested {
+        if !oneofs.is_empty() {
+            out.push('\n');
+        }
+        render_items(model, Some(&m.name), out);
+    }
+    out.push_str(&format!("{}}}\n", pad));
+}
+
+fn render_enum(e: &Enum, out: &mut String) {
+    let pad = "    ".repeat(depth(&e.name));
+    let name = simple_name(&e.name);
+    out.push_str(&format!(
+        "{}#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]\n",
+        pad
+    ));
+    out.push_str(&format!("{}#[repr(i32)]\n", pad));
+    out.push_str(&format!("{}pub enum {} {{\n", pad, name));
+    // like prost-build: drop the `ENUM_NAME_` prefix, keep only the first alias
+    let prefix = format!("{}_", screaming_snake(name));
+    let mut seen = Vec::new();
+    for v in &e.values {
+        if seen.contains(&v.number) {
+            continue;
+        }
+        seen.push(v.number);
+        let variant = v
+            .name
+            .strip_prefix(&prefix)
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .unwrap_or(&v.name);
+        out.push_str(&format!(
+            "{}    {} = {},\n",
+            pad,
+            sanitize_ident(&upper_camel(variant)),
+            v.number
+        ));
+    }
+    out.push_str(&format!("{}}}\n", pad));
+}
+
+// `#[prost(...)]` contents and Rust type of a (non-oneof) struct field.
+fn field_attr(model: &ProtoModel, m: &Message, f: &Field, module: &[String]) -> (String, String) {
+    let (kind, ty) = field_kind(model, m, &f.ty, module);
+    let is_message = kind == "message";
+    let tag = format!("tag = \"{}\"", f.order);
+    match f.label {
+        Some(FieldLabel::Repeated) => {
+            // proto2 packs repeated scalars only when asked to
+            let unpacked = model.syntax == ProtoSyntax::Proto2
+                && is_packable(&f.ty, &kind)
+                && f.option("packed") != Some(&parser::OptionValue::Bool(true));
+            let packed = if unpacked { "packed = \"false\", " } else { "" };
+            (
+                format!("{}, repeated, {}{}", kind, packed, tag),
+                format!("Vec<{}>", ty),
+            )
+        }
+        label if is_message => {
+            let presence = match label {
+                Some(FieldLabel::Required) => "required",
+                _ => "optional",
+            };
+            if needs_box(model, m, &f.ty) {
+                (
+                    format!("{}, {}, boxed, {}", kind, presence, tag),
+                    format!("Option<Box<{}>>", ty),
+                )
+            } else {
+                (
+                    format!("{}, {}, {}", kind, presence, tag),
+                    format!("Option<{}>", ty),
+                )
+            }
+        }
+        Some(FieldLabel::Optional) => (
+            format!("{}, optional, {}", kind, tag),
+            format!("Option<{}>", ty),
+        ),
+        Some(FieldLabel::Required) => (format!("{}, required, {}", kind, tag), ty),
+        None => (format!("{}, {}", kind, tag), ty),
+    }
+}
+
+// A message-typed field whose message (directly or through further singular
+// fields) contains `m` again needs indirection, as prost-build boxes it.
+fn needs_box(model: &ProtoModel, m: &Message, ft: &FieldType) -> bool {
+    let Some(target) = local_message(model, &m.name, ft) else {
+        return false;
+    };
+    let mut seen = Vec::new();
+    reaches(model, target, &m.name, &mut seen)
+}
+
+// Whether a `from` value holds a `to` inline: it is one, or one of its
+// singular (including oneof) message fields does.
+fn reaches<'a>(
+    model: &'a ProtoModel,
+    from: &'a Message,
+    to: &str,
+    seen: &mut Vec<&'a str>,
+) -> bool {
+    if from.name == to {
+        return true;
+    }
+    if seen.contains(&from.name.as_str()) {
+        return false;
+    }
+    seen.push(&from.name);
+    from.fields
+        .iter()
+        .filter(|f| f.label != Some(FieldLabel::Repeated))
+        .filter_map(|f| local_message(model, &from.name, &f.ty))
+        .any(|next| reaches(model, next, to, seen))
+}
+
+// The message declared in this file that a field type refers to, if any.
+// Map values live in a HashMap, so they never need boxing.
+fn local_message<'a>(model: &'a ProtoModel, scope: &str, ft: &FieldType) -> Option<&'a Message> {
+    if matches!(ft, FieldType::Map { .. }) {
+        return None;
+    }
+    let declared = model.resolve_type_name(scope, ft.type_name()?)?;
+    model.types.iter().find_map(|t| match t {
+        TypeDecl::Message(msg) if msg.name == declared => Some(msg),
+        _ => None,
+    })
+}
+
+// prost type kind (`int32`, `message`, `enumeration = "..."`) and Rust type.
+fn field_kind(
+    model: &ProtoModel,
+    m: &Message,
+    ft: &FieldType,
+    module: &[String],
+) -> (String, String) {
+    let name = match ft {
+        FieldType::Scalar(st) => {
+            let (kind, ty) = scalar(st);
+            return (kind.to_string(), ty.to_string());
+        }
+        FieldType::Custom(name) | FieldType::Message(name) | FieldType::Enum(name) => name,
+        FieldType::Map { key, value } => {
+            let (key_kind, key_ty) = scalar(key);
+            let (value_kind, value_ty) = field_kind(model, m, value, module);
+            // inside `map = "..."` prost spells `enumeration = "X"` as
+            // `enumeration(X)` and takes bytes without a representation
+            let value_kind = match value_kind.strip_prefix("enumeration = ") {
+                Some(path) => format!("enumeration({})", path.trim_matches('"')),
+                None => value_kind.replace("bytes = \"vec\"", "bytes"),
+            };
+            return (
+                format!("map = \"{}, {}\"", key_kind, value_kind),
+                format!("::std::collections::HashMap<{}, {}>", key_ty, value_ty),
+            );
+        }
+    };
+    let Some(declared) = model.resolve_type_name(&m.name, name) else {
+        // not declared here: assume a message in scope by its simple name
+        return ("message".into(), simple_name(name).to_string());
+    };
+    let path = relative_path(module, &declared);
+    let is_enum = model
+        .types
+        .iter()
+        .any(|t| matches!(t, TypeDecl::Enum(e) if e.name == declared));
+    if is_enum {
+        (format!("enumeration = \"{}\"", path), "i32".into())
+    } else {
+        ("message".into(), path)
+    }
+}
+
+fn scalar(st: &ScalarType) -> (&'static str, &'static str) {
+    match st {
+        ScalarType::Double => ("double", "f64"),
+        ScalarType::Float => ("float", "f32"),
+        ScalarType::Int32 => ("int32", "i32"),
+        ScalarType::Int64 => ("int64", "i64"),
+        ScalarType::Uint32 => ("uint32", "u32"),
+        ScalarType::Uint64 => ("uint64", "u64"),
+        ScalarType::Sint32 => ("sint32", "i32"),
+        ScalarType::Sint64 => ("sint64", "i64"),
+        ScalarType::Fixed32 => ("fixed32", "u32"),
+        ScalarType::Fixed64 => ("fixed64", "u64"),
+        ScalarType::Sfixed32 => ("sfixed32", "i32"),
+        ScalarType::Sfixed64 => ("sfixed64", "i64"),
+        ScalarType::Bool => ("bool", "bool"),
+        ScalarType::String => ("string", "String"),
+        ScalarType::Bytes => ("bytes = \"vec\"", "Vec<u8>"),
+    }
+}
+
+// Numeric scalars and enums can be packed; strings, bytes and messages can't.
+fn is_packable(ft: &FieldType, kind: &str) -> bool {
+    match ft {
+        FieldType::Scalar(st) => !matches!(st, ScalarType::String | ScalarType::Bytes),
+        FieldType::Custom(_) | FieldType::Message(_) | FieldType::Enum(_) => {
+            kind.starts_with("enumeration")
+        }
+        FieldType::Map { .. } => false,
+    }
+}
+
+// Path to the declared type from inside `module`, via `super::` as needed.
+fn relative_path(module: &[String], declared: &str) -> String {
+    let target = module_path(declared);
+    let common = module
+        .iter()
+        .zip(&target)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut segments: Vec<String> = vec!["super".to_string(); module.len() - common];
+    segments.extend(target[common..].iter().cloned());
+    segments.push(simple_name(declared).to_string());
+    segments.join("::")
+}
+
+// Modules enclosing a declared type: `Order.Line.Unit` lives in `order::line`.
+fn module_path(declared: &str) -> Vec<String> {
+    let mut parts: Vec<&str> = declared.split('.').collect();
+    parts.pop();
+    parts.into_iter().map(snake_case).collect()
+}
+
+fn nested_module(declared: &str) -> String {
+    snake_case(simple_name(declared))
+}
+
+fn depth(declared: &str) -> usize {
+    declared.matches('.').count()
+}
+
+fn simple_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+// Keywords as prost-build escapes them: strict and reserved ones become raw
+// identifiers, those that cannot be raw get a `_` suffix.
+fn sanitize_ident(name: &str) -> String {
+    const KEYWORDS: [&str; 47] = [
+        "as", "break", "const", "continue", "else", "enum", "false", "fn", "for", "if", "impl",
+        "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+        "struct", "trait", "true", "type", "unsafe", "use", "where", "while", "dyn", "abstract",
+        "become", "box", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+        "virtual", "yield", "async", "await", "try", "gen",
+    ];
+    match name {
+        "_" | "super" | "self" | "Self" | "extern" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+// `OrderItem` -> `order_item`
+fn snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else {
+            out.push(c);
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        }
+    }
+    out
+}
+
+// `OrderItem` -> `ORDER_ITEM`
+fn screaming_snake(s: &str) -> String {
+    snake_case(s).to_ascii_uppercase()
+}
+
+// `price_cents` / `PAID_IN_FULL` -> `PriceCents` / `PaidInFull`
+fn upper_camel(s: &str) -> String {
+    s.split('_')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let p = p.to_lowercase();
+            let mut it = p.chars();
+            match it.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + it.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// Test module.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prost_attributes_carry_tags_and_wire_types() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            package shop;
+            message Order {
+                int64 id = 1;
+                sint32 delta = 2;
+                repeated string tags = 3;
+                optional bytes signature = 4;
+                Status status = 5;
+                repeated Line lines = 6;
+                Order parent = 7;
+                oneof payment {
+                    string card = 8;
+                    fixed64 voucher = 9;
+                }
+                string type = 10;
+                enum Status { STATUS_UNKNOWN = 0; STATUS_PAID_IN_FULL = 1; }
+                message Line { uint32 qty = 1; Status status = 2; }
+            }
+        "#,
+        )
+        .unwrap();
+        let files = generate_rust_from_model(&model);
+        assert_eq!(files.len(), 1);
+        let (name, src) = &files[0];
+        assert_eq!(name, "shop.rs");
+
+        let expected = r#"#[derive(Clone, PartialEq, prost::Message)]
+pub struct Order {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(sint32, tag = "2")]
+    pub delta: i32,
+    #[prost(string, repeated, tag = "3")]
+    pub tags: Vec<String>,
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub signature: Option<Vec<u8>>,
+    #[prost(enumeration = "order::Status", tag = "5")]
+    pub status: i32,
+    #[prost(message, repeated, tag = "6")]
+    pub lines: Vec<order::Line>,
+    #[prost(message, optional, boxed, tag = "7")]
+    pub parent: Option<Box<Order>>,
+    #[prost(string, tag = "10")]
+    pub r#type: String,
+    #[prost(oneof = "order::Payment", tags = "8, 9")]
+    pub payment: Option<order::Payment>,
+}
+/// Nested message and enum types in `Order`.
+pub mod order {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Payment {
+        #[prost(string, tag = "8")]
+        Card(String),
+        #[prost(fixed64, tag = "9")]
+        Voucher(u64),
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Status {
+        Unknown = 0,
+        PaidInFull = 1,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Line {
+        #[prost(uint32, tag = "1")]
+        pub qty: u32,
+        #[prost(enumeration = "Status", tag = "2")]
+        pub status: i32,
+    }
+}
+"#;
+        assert_eq!(src, expected);
+    }
+
+    #[test]
+    fn proto2_repeated_scalars_are_unpacked() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto2";
+            message Sample {
+                repeated int32 plain = 1;
+                repeated int32 packed = 2 [packed = true];
+                required double weight = 3;
+            }
+        "#,
+        )
+        .unwrap();
+        let (name, src) = generate_rust_from_model(&model).remove(0);
+        assert_eq!(name, "_.rs");
+        assert!(src.contains("    #[prost(int32, repeated, packed = \"false\", tag = \"1\")]\n"));
+        assert!(src.contains("    #[prost(int32, repeated, tag = \"2\")]\n"));
+        assert!(
+            src.contains("    #[prost(double, required, tag = \"3\")]\n    pub weight: f64,\n")
+        );
+    }
+
+    #[test]
+    fn map_fields_become_hash_maps() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Order {
+                enum Status { NEW = 0; }
+                message Line { string sku = 1; }
+                map<string, Status> by_region = 1;
+                map<int32, Line> lines = 2;
+                map<string, bytes> blobs = 3;
+            }
+        "#,
+        )
+        .unwrap();
+        let (_, src) = generate_rust_from_model(&model).remove(0);
+        assert!(src.contains(
+            "    #[prost(map = \"string, enumeration(order::Status)\", tag = \"1\")]\n    \
+             pub by_region: ::std::collections::HashMap<String, i32>,\n"
+        ));
+        assert!(src.contains(
+            "    #[prost(map = \"int32, message\", tag = \"2\")]\n    \
+             pub lines: ::std::collections::HashMap<i32, order::Line>,\n"
+        ));
+        assert!(src.contains("    #[prost(map = \"string, bytes\", tag = \"3\")]\n"));
+    }
+
+    #[test]
+    fn keywords_are_escaped_like_prost_build() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Names {
+                string crate = 1;
+                string self = 2;
+                string super = 3;
+                string box = 4;
+                string yield = 5;
+                string gen = 6;
+                string extern = 7;
+                oneof kind { string try = 8; int32 Self = 9; }
+            }
+        "#,
+        )
+        .unwrap();
+        let (_, src) = generate_rust_from_model(&model).remove(0);
+        for field in [
+            "crate_", "self_", "super_", "r#box", "r#yield", "r#gen", "extern_",
+        ] {
+            assert!(
+                src.contains(&format!("    pub {}: String,\n", field)),
+                "{}",
+                field
+            );
+        }
+        assert!(src.contains("        Try(String),\n"));
+        assert!(src.contains("        Self_(i32),\n"));
+    }
+
+    #[test]
+    fn messages_in_a_cycle_are_boxed() {
+        let model = parser::parse_proto_str(
+            r#"
+            syntax = "proto3";
+            message Node { Edge out = 1; repeated Node children = 2; map<string, Node> by_name = 3; }
+            message Edge { Node to = 1; Leaf leaf = 2; oneof via { Node node = 3; } }
+            message Leaf { string id = 1; }
+        "#,
+        )
+        .unwrap();
+        let (_, src) = generate_rust_from_model(&model).remove(0);
+        assert!(src.contains(
+            "    #[prost(message, optional, boxed, tag = \"1\")]\n    pub out: Option<Box<Edge>>,\n"
+        ));
+        assert!(src.contains(
+            "    #[prost(message, optional, boxed, tag = \"1\")]\n    pub to: Option<Box<Node>>,\n"
+        ));
+        assert!(src.contains(
+            "        #[prost(message, boxed, tag = \"3\")]\n        Node(Box<super::Node>),\n"
+        ));
+        // no cycle through Leaf; Vec and HashMap already add indirection
+        assert!(src.contains("    pub leaf: Option<Leaf>,\n"));
+        assert!(src.contains("    pub children: Vec<Node>,\n"));
+        assert!(src.contains("    pub by_name: ::std::collections::HashMap<String, Node>,\n"));
+    }
+}